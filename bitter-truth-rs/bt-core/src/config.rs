@@ -0,0 +1,200 @@
+// Layered tool configuration: defaults < TOML file < BT_* env vars
+
+use crate::output::{self, OutputLimit, DEFAULT_MAX_OUTPUT_BYTES};
+use crate::{redact, set_log_level, trace, BtError, Context, LogLevel, TraceContext};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+/// Settings shared by all bitter-truth tools
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ToolConfig {
+    /// Model used by generate when the input does not name one
+    pub model: String,
+    /// Directory where generate writes code when no output_path is given
+    pub output_dir: String,
+    /// Timeout applied when the input context does not carry one
+    pub timeout_seconds: u64,
+    /// Minimum level written by `log_stderr`
+    pub log_level: String,
+    /// Responses larger than this spill `data` to a file in `output_dir`
+    pub max_output_bytes: usize,
+    /// Extra field-name fragments masked in logs, on top of the built-in list
+    pub redact_keys: Vec<String>,
+}
+
+impl Default for ToolConfig {
+    fn default() -> Self {
+        Self {
+            model: "anthropic/claude-opus-4-5".to_string(),
+            output_dir: "/tmp".to_string(),
+            timeout_seconds: 300,
+            log_level: "info".to_string(),
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            redact_keys: Vec::new(),
+        }
+    }
+}
+
+impl ToolConfig {
+    /// Load defaults, then the config file (if any), then `BT_*` env overrides.
+    ///
+    /// See [`config_path`] for where the file is looked up.
+    pub fn load() -> Result<Self, BtError> {
+        let mut config = match config_path()? {
+            Some(path) => Self::from_file(&path)?,
+            None => Self::default(),
+        };
+        config.apply_env(|key| std::env::var(key).ok())?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &std::path::Path) -> Result<Self, BtError> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            BtError::InvalidInput(format!("Failed to read config {}: {}", path.display(), e))
+        })?;
+        Self::from_toml_str(&content)
+            .map_err(|e| BtError::InvalidInput(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn from_toml_str(content: &str) -> Result<Self, BtError> {
+        toml::from_str(content).map_err(|e| BtError::InvalidInput(format!("Invalid config: {}", e)))
+    }
+
+    /// Apply `BT_MODEL`, `BT_OUTPUT_DIR`, `BT_TIMEOUT_SECONDS`, `BT_LOG_LEVEL`,
+    /// `BT_MAX_OUTPUT_BYTES` and `BT_REDACT_KEYS` (comma-separated, appended)
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), BtError> {
+        if let Some(model) = var("BT_MODEL") {
+            self.model = model;
+        }
+        if let Some(dir) = var("BT_OUTPUT_DIR") {
+            self.output_dir = dir;
+        }
+        if let Some(timeout) = var("BT_TIMEOUT_SECONDS") {
+            self.timeout_seconds = timeout.parse().map_err(|_| {
+                BtError::InvalidInput(format!("BT_TIMEOUT_SECONDS is not a number: {}", timeout))
+            })?;
+        }
+        if let Some(level) = var("BT_LOG_LEVEL") {
+            self.log_level = level;
+        }
+        if let Some(max) = var("BT_MAX_OUTPUT_BYTES") {
+            self.max_output_bytes = max.parse().map_err(|_| {
+                BtError::InvalidInput(format!("BT_MAX_OUTPUT_BYTES is not a number: {}", max))
+            })?;
+        }
+        if let Some(keys) = var("BT_REDACT_KEYS") {
+            self.redact_keys.extend(
+                keys.split(',')
+                    .map(|k| k.trim().to_string())
+                    .filter(|k| !k.is_empty()),
+            );
+        }
+        Ok(())
+    }
+
+    /// Fill context fields the caller left unset and install logging settings
+    pub fn apply_to(&self, context: &mut Context) {
+        if context.timeout_seconds.is_none() {
+            context.timeout_seconds = Some(self.timeout_seconds);
+        }
+        context.resolve_trace(|key| std::env::var(key).ok());
+        trace::set_trace_context(TraceContext {
+            traceparent: context.traceparent.clone(),
+            tracestate: context.tracestate.clone(),
+        });
+        let level = context
+            .log_level
+            .get_or_insert_with(|| self.log_level.clone());
+        if let Some(level) = LogLevel::parse(level) {
+            set_log_level(level);
+        }
+        redact::add_sensitive_keys(self.redact_keys.iter().cloned());
+        output::set_output_limit(OutputLimit {
+            max_bytes: self.max_output_bytes,
+            spill_dir: PathBuf::from(&self.output_dir),
+        });
+    }
+}
+
+/// The config file: `$BT_CONFIG` when set, otherwise
+/// `$XDG_CONFIG_HOME/bitter-truth/config.toml` (or `~/.config/...`).
+/// A missing default file yields `None`; a missing `$BT_CONFIG` is an error.
+pub fn config_path() -> Result<Option<PathBuf>, BtError> {
+    match std::env::var("BT_CONFIG") {
+        Ok(path) => {
+            let path = PathBuf::from(path);
+            if !path.exists() {
+                return Err(BtError::NotFound(format!(
+                    "Config not found: {}",
+                    path.display()
+                )));
+            }
+            Ok(Some(path))
+        }
+        Err(_) => Ok(default_config_path().filter(|p| p.exists())),
+    }
+}
+
+/// Load a tool-specific table (e.g. `[gate1]`) from the shared config file,
+/// falling back to `T::default()` when the file or table is absent
+pub fn load_section<T: DeserializeOwned + Default>(section: &str) -> Result<T, BtError> {
+    let Some(path) = config_path()? else {
+        return Ok(T::default());
+    };
+    let content = std::fs::read_to_string(&path).map_err(|e| {
+        BtError::InvalidInput(format!("Failed to read config {}: {}", path.display(), e))
+    })?;
+    section_from_toml_str(&content, section)
+        .map_err(|e| BtError::InvalidInput(format!("{}: {}", path.display(), e)))
+}
+
+pub fn section_from_toml_str<T: DeserializeOwned + Default>(
+    content: &str,
+    section: &str,
+) -> Result<T, BtError> {
+    let mut table: toml::Table = toml::from_str(content)
+        .map_err(|e| BtError::InvalidInput(format!("Invalid config: {}", e)))?;
+    match table.remove(section) {
+        Some(value) => value
+            .try_into()
+            .map_err(|e| BtError::InvalidInput(format!("Invalid [{}] config: {}", section, e))),
+        None => Ok(T::default()),
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(base.join("bitter-truth").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_then_env_layering() {
+        let mut config =
+            ToolConfig::from_toml_str("model = \"local/qwen\"\ntimeout_seconds = 60\n").unwrap();
+        assert_eq!(config.model, "local/qwen");
+        assert_eq!(config.output_dir, "/tmp");
+
+        config
+            .apply_env(|k| (k == "BT_TIMEOUT_SECONDS").then(|| "5".to_string()))
+            .unwrap();
+        assert_eq!(config.timeout_seconds, 5);
+        assert_eq!(config.model, "local/qwen");
+    }
+
+    #[test]
+    fn test_bad_env_timeout_is_invalid_input() {
+        let mut config = ToolConfig::default();
+        let err = config
+            .apply_env(|k| (k == "BT_TIMEOUT_SECONDS").then(|| "soon".to_string()))
+            .unwrap_err();
+        assert_eq!(err.code(), crate::ErrorCode::InvalidInput);
+    }
+}
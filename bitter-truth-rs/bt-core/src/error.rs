@@ -0,0 +1,186 @@
+// Typed errors shared by all bitter-truth tools
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Stable, machine-readable error classes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// Tool input was malformed or missing required fields
+    InvalidInput,
+    /// A referenced file (contract, code, output) does not exist
+    NotFound,
+    /// An external binary the tool relies on is unavailable
+    DependencyMissing,
+    /// Output does not satisfy its contract
+    ContractViolation,
+    /// Generated code did not pass a quality gate
+    GateFailed,
+    /// An operation exceeded its time budget
+    Timeout,
+    /// Filesystem or pipe failure
+    Io,
+    /// Anything else
+    Internal,
+}
+
+impl ErrorCode {
+    /// Whether another attempt can plausibly succeed without human intervention.
+    ///
+    /// Contract and gate failures are healed by regenerating with feedback;
+    /// timeouts and I/O failures are transient infrastructure problems.
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::ContractViolation
+                | ErrorCode::GateFailed
+                | ErrorCode::Timeout
+                | ErrorCode::Io
+        )
+    }
+}
+
+/// Error type returned by tool logic
+#[derive(Debug)]
+pub enum BtError {
+    InvalidInput(String),
+    NotFound(String),
+    DependencyMissing(String),
+    ContractViolation(String),
+    GateFailed(String),
+    Timeout(String),
+    Io(String),
+    Internal(String),
+}
+
+impl BtError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            BtError::InvalidInput(_) => ErrorCode::InvalidInput,
+            BtError::NotFound(_) => ErrorCode::NotFound,
+            BtError::DependencyMissing(_) => ErrorCode::DependencyMissing,
+            BtError::ContractViolation(_) => ErrorCode::ContractViolation,
+            BtError::GateFailed(_) => ErrorCode::GateFailed,
+            BtError::Timeout(_) => ErrorCode::Timeout,
+            BtError::Io(_) => ErrorCode::Io,
+            BtError::Internal(_) => ErrorCode::Internal,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            BtError::InvalidInput(m)
+            | BtError::NotFound(m)
+            | BtError::DependencyMissing(m)
+            | BtError::ContractViolation(m)
+            | BtError::GateFailed(m)
+            | BtError::Timeout(m)
+            | BtError::Io(m)
+            | BtError::Internal(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for BtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for BtError {}
+
+impl From<std::io::Error> for BtError {
+    fn from(e: std::io::Error) -> Self {
+        BtError::Io(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for BtError {
+    fn from(e: serde_json::Error) -> Self {
+        BtError::InvalidInput(format!("Invalid JSON: {}", e))
+    }
+}
+
+/// Recovers a `BtError` carried inside an anyhow chain, otherwise `Internal`
+impl From<anyhow::Error> for BtError {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast::<BtError>() {
+            Ok(bt) => bt,
+            Err(e) => BtError::Internal(format!("{:#}", e)),
+        }
+    }
+}
+
+/// Error payload of a failed `ToolResponse`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub retryable: bool,
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub details: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ToolError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            retryable: code.is_retryable(),
+            details: serde_json::Map::new(),
+        }
+    }
+
+    pub fn with_detail(mut self, key: &str, value: serde_json::Value) -> Self {
+        self.details.insert(key.to_string(), value);
+        self
+    }
+}
+
+impl From<BtError> for ToolError {
+    fn from(e: BtError) -> Self {
+        ToolError::new(e.code(), e.message())
+    }
+}
+
+impl From<std::io::Error> for ToolError {
+    fn from(e: std::io::Error) -> Self {
+        BtError::from(e).into()
+    }
+}
+
+impl From<anyhow::Error> for ToolError {
+    fn from(e: anyhow::Error) -> Self {
+        BtError::from(e).into()
+    }
+}
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_error_serializes_code_and_retryable() {
+        let err: ToolError = BtError::GateFailed("syntax".to_string()).into();
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["code"], "GATE_FAILED");
+        assert_eq!(json["retryable"], true);
+        assert!(json.get("details").is_none());
+    }
+
+    #[test]
+    fn test_anyhow_roundtrip_keeps_code() {
+        let e: anyhow::Error = BtError::DependencyMissing("opencode".to_string()).into();
+        assert_eq!(BtError::from(e).code(), ErrorCode::DependencyMissing);
+
+        let e = anyhow::anyhow!("boom");
+        assert_eq!(BtError::from(e).code(), ErrorCode::Internal);
+    }
+}
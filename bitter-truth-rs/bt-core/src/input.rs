@@ -0,0 +1,92 @@
+// Where a tool reads its JSON input from
+
+use crate::BtError;
+use std::io::Read;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputSource {
+    Stdin,
+    File(PathBuf),
+}
+
+impl InputSource {
+    /// `--input <file>` (or `--input=<file>`) wins over `BT_INPUT_PATH`,
+    /// which wins over stdin. A path of `-` means stdin.
+    pub fn resolve(
+        args: impl IntoIterator<Item = String>,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, BtError> {
+        let mut args = args.into_iter();
+        let mut flag = None;
+        while let Some(arg) = args.next() {
+            if arg == "--input" {
+                let path = args.next().ok_or_else(|| {
+                    BtError::InvalidInput("--input requires a file path".to_string())
+                })?;
+                flag = Some(path);
+            } else if let Some(path) = arg.strip_prefix("--input=") {
+                flag = Some(path.to_string());
+            }
+        }
+
+        match flag.or_else(|| var("BT_INPUT_PATH").filter(|p| !p.is_empty())) {
+            Some(path) if path != "-" => Ok(InputSource::File(PathBuf::from(path))),
+            _ => Ok(InputSource::Stdin),
+        }
+    }
+
+    pub fn read(&self) -> Result<String, BtError> {
+        match self {
+            InputSource::Stdin => {
+                let mut raw = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut raw)
+                    .map_err(|e| BtError::Io(format!("Failed to read input from stdin: {}", e)))?;
+                Ok(raw)
+            }
+            InputSource::File(path) => std::fs::read_to_string(path).map_err(|e| {
+                let msg = format!("Failed to read input from {}: {}", path.display(), e);
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    BtError::NotFound(msg)
+                } else {
+                    BtError::Io(msg)
+                }
+            }),
+        }
+    }
+}
+
+/// Read the tool input from the process arguments, environment, or stdin
+pub fn read_input() -> Result<String, BtError> {
+    InputSource::resolve(std::env::args().skip(1), |key| std::env::var(key).ok())?.read()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let env = |k: &str| (k == "BT_INPUT_PATH").then(|| "/env.json".to_string());
+        let no_env = |_: &str| None;
+
+        assert_eq!(
+            InputSource::resolve(args(&["--input", "/flag.json"]), env).unwrap(),
+            InputSource::File(PathBuf::from("/flag.json"))
+        );
+        assert_eq!(
+            InputSource::resolve(args(&[]), env).unwrap(),
+            InputSource::File(PathBuf::from("/env.json"))
+        );
+        assert_eq!(
+            InputSource::resolve(args(&["--input=-"]), no_env).unwrap(),
+            InputSource::Stdin
+        );
+        assert!(InputSource::resolve(args(&["--input"]), no_env).is_err());
+    }
+}
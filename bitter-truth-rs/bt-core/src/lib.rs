@@ -1,7 +1,30 @@
 // Bitter-Truth Core Library
 // Shared types and utilities for all bitter-truth tools
 
+pub mod config;
+mod error;
+pub mod input;
+pub mod output;
+pub mod process;
+pub mod redact;
+pub mod retry;
+mod span;
+mod tool;
+pub mod trace;
+pub mod usage;
+
+pub use config::ToolConfig;
+pub use error::{BtError, ErrorCode, ToolError};
+pub use input::{read_input, InputSource};
+pub use output::OutputLimit;
+pub use retry::{Backoff, RetryPolicy};
+pub use span::Span;
+pub use tool::{execute, run};
+pub use trace::TraceContext;
+pub use usage::Usage;
+
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::time::SystemTime;
 
 /// Common context for all tools
@@ -10,6 +33,14 @@ pub struct Context {
     pub trace_id: String,
     pub dry_run: bool,
     pub timeout_seconds: Option<u64>,
+    /// Overrides `BT_LOG_LEVEL` for this invocation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+    /// W3C trace context; falls back to the `TRACEPARENT`/`TRACESTATE` env vars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracestate: Option<String>,
 }
 
 impl Default for Context {
@@ -17,7 +48,28 @@ impl Default for Context {
         Self {
             trace_id: uuid::Uuid::new_v4().to_string()[..8].to_string(),
             dry_run: false,
-            timeout_seconds: Some(300),
+            // Filled from the config (`timeout_seconds`, `BT_TIMEOUT_SECONDS`) by `ToolConfig::apply_to`
+            timeout_seconds: None,
+            log_level: None,
+            traceparent: None,
+            tracestate: None,
+        }
+    }
+}
+
+impl Context {
+    /// Fill missing trace context from the environment, dropping a malformed
+    /// `traceparent` (and its `tracestate`) rather than propagating it
+    pub fn resolve_trace(&mut self, var: impl Fn(&str) -> Option<String>) {
+        if self.traceparent.is_none() {
+            self.traceparent = var("TRACEPARENT");
+            if self.tracestate.is_none() {
+                self.tracestate = var("TRACESTATE");
+            }
+        }
+        if !self.traceparent.as_deref().is_some_and(trace::is_valid_traceparent) {
+            self.traceparent = None;
+            self.tracestate = None;
         }
     }
 }
@@ -29,56 +81,150 @@ pub struct ToolResponse<T> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<String>,
+    pub error: Option<ToolError>,
+    /// Set instead of `data` when the payload exceeded the output limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_path: Option<String>,
+    /// Tokens and spend recorded via `usage::record` during the run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
     pub trace_id: String,
     pub duration_ms: f64,
 }
 
+impl<T> ToolResponse<T> {
+    pub fn ok(data: T, trace_id: String, duration_ms: f64) -> Self {
+        Self {
+            success: true,
+            data: Some(data),
+            error: None,
+            data_path: None,
+            usage: None,
+            trace_id,
+            duration_ms,
+        }
+    }
+
+    pub fn failure(error: ToolError, trace_id: String, duration_ms: f64) -> Self {
+        Self {
+            success: false,
+            data: None,
+            error: Some(error),
+            data_path: None,
+            usage: None,
+            trace_id,
+            duration_ms,
+        }
+    }
+}
+
 /// Log entry for stderr output
 #[derive(Debug, Serialize)]
 pub struct LogEntry {
     pub level: String,
     pub msg: String,
     pub trace_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracestate: Option<String>,
     #[serde(flatten)]
     pub extra: serde_json::Value,
 }
 
 impl LogEntry {
-    pub fn info(msg: impl Into<String>, trace_id: String) -> Self {
+    fn new(level: &str, msg: impl Into<String>, trace_id: String) -> Self {
+        let trace = trace::trace_context();
         Self {
-            level: "info".to_string(),
-            msg: msg.into(),
+            level: level.to_string(),
+            msg: redact::redact_text(&msg.into()),
             trace_id,
+            traceparent: trace.traceparent,
+            tracestate: trace.tracestate,
             extra: serde_json::json!({}),
         }
     }
 
+    pub fn info(msg: impl Into<String>, trace_id: String) -> Self {
+        Self::new("info", msg, trace_id)
+    }
+
     pub fn error(msg: impl Into<String>, trace_id: String) -> Self {
-        Self {
-            level: "error".to_string(),
-            msg: msg.into(),
-            trace_id,
-            extra: serde_json::json!({}),
-        }
+        Self::new("error", msg, trace_id)
+    }
+
+    pub fn warn(msg: impl Into<String>, trace_id: String) -> Self {
+        Self::new("warn", msg, trace_id)
     }
 
     pub fn debug(msg: impl Into<String>, trace_id: String) -> Self {
-        Self {
-            level: "debug".to_string(),
-            msg: msg.into(),
-            trace_id,
-            extra: serde_json::json!({}),
-        }
+        Self::new("debug", msg, trace_id)
     }
 
+    /// Attach a field; sensitive keys and embedded credentials are masked
     pub fn with_extra(mut self, key: &str, value: serde_json::Value) -> Self {
+        let value = redact::redact_value(key, value);
         self.extra.as_object_mut().unwrap().insert(key.to_string(), value);
         self
     }
 }
 
+/// Severity of a log entry, ordered from most to least verbose
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug = 1,
+    Info = 2,
+    Warn = 3,
+    Error = 4,
+}
+
+impl LogLevel {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "debug" | "trace" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warn" | "warning" => Some(LogLevel::Warn),
+            "error" => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+}
+
+// 0 = not yet initialized from BT_LOG_LEVEL
+static MIN_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+/// Set the minimum level written by `log_stderr`
+pub fn set_log_level(level: LogLevel) {
+    MIN_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Current minimum level; defaults to `BT_LOG_LEVEL`, then info
+pub fn log_level() -> LogLevel {
+    match MIN_LEVEL.load(Ordering::Relaxed) {
+        1 => LogLevel::Debug,
+        2 => LogLevel::Info,
+        3 => LogLevel::Warn,
+        4 => LogLevel::Error,
+        _ => {
+            let level = std::env::var("BT_LOG_LEVEL")
+                .ok()
+                .and_then(|l| LogLevel::parse(&l))
+                .unwrap_or(LogLevel::Info);
+            set_log_level(level);
+            level
+        }
+    }
+}
+
+/// Whether `entry` is at or above `min`; unknown levels always are
+fn passes(entry: &LogEntry, min: LogLevel) -> bool {
+    LogLevel::parse(&entry.level).is_none_or(|l| l >= min)
+}
+
 pub fn log_stderr(entry: &LogEntry) {
+    if !passes(entry, log_level()) {
+        return;
+    }
     if let Ok(json) = serde_json::to_string(entry) {
         eprintln!("{}", json);
     }
@@ -91,28 +237,53 @@ pub fn elapsed_ms(start: SystemTime) -> f64 {
         .as_millis() as f64
 }
 
-/// Exit with success response
+/// Exit with success response, spilling oversized data to a file
 pub fn success_exit<T: Serialize>(data: T, trace_id: String, start: SystemTime) {
-    let response = ToolResponse {
-        success: true,
-        data: Some(data),
-        error: None,
-        trace_id,
-        duration_ms: elapsed_ms(start),
-    };
-    println!("{}", serde_json::to_string(&response).unwrap());
-    std::process::exit(0);
+    let mut response = ToolResponse::ok(data, trace_id, elapsed_ms(start));
+    response.usage = usage::take();
+    std::process::exit(tool::print_response(response));
 }
 
 /// Exit with error response
-pub fn error_exit(error: String, trace_id: String, start: SystemTime) -> ! {
-    let response: ToolResponse<()> = ToolResponse {
-        success: false,
-        data: None,
-        error: Some(error),
-        trace_id,
-        duration_ms: elapsed_ms(start),
-    };
-    println!("{}", serde_json::to_string(&response).unwrap());
-    std::process::exit(1);
+pub fn error_exit(error: impl Into<ToolError>, trace_id: String, start: SystemTime) -> ! {
+    let mut response: ToolResponse<()> = ToolResponse::failure(error.into(), trace_id, elapsed_ms(start));
+    response.usage = usage::take();
+    std::process::exit(tool::print_response(response));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_below_min_level_are_dropped() {
+        let trace = || "t".to_string();
+        assert!(!passes(&LogEntry::debug("d", trace()), LogLevel::Info));
+        assert!(passes(&LogEntry::info("i", trace()), LogLevel::Info));
+        assert!(passes(&LogEntry::error("e", trace()), LogLevel::Warn));
+        assert!(!passes(&LogEntry::warn("w", trace()), LogLevel::Error));
+        let notice = LogEntry::new("notice", "n", trace());
+        assert!(passes(&notice, LogLevel::Error));
+        assert_eq!(LogLevel::parse(" WARNING "), Some(LogLevel::Warn));
+    }
+
+    #[test]
+    fn test_context_log_level_overrides_config() {
+        let config = config::ToolConfig {
+            log_level: "debug".to_string(),
+            ..Default::default()
+        };
+        let mut context = Context {
+            log_level: Some("error".to_string()),
+            ..Context::default()
+        };
+        config.apply_to(&mut context);
+        let min = LogLevel::parse(context.log_level.as_deref().unwrap()).unwrap();
+        assert_eq!(min, LogLevel::Error);
+        assert!(!passes(&LogEntry::warn("w", "t".to_string()), min));
+
+        let mut unset = Context::default();
+        config.apply_to(&mut unset);
+        assert_eq!(unset.log_level.as_deref(), Some("debug"));
+    }
 }
@@ -0,0 +1,126 @@
+// Size limits for the response printed on stdout
+
+use crate::{BtError, ToolResponse};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// Kestra rejects task outputs much beyond this
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Largest response printed inline, and where larger `data` is spilled
+#[derive(Debug, Clone)]
+pub struct OutputLimit {
+    pub max_bytes: usize,
+    pub spill_dir: PathBuf,
+}
+
+impl Default for OutputLimit {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            spill_dir: std::env::temp_dir(),
+        }
+    }
+}
+
+static LIMIT: RwLock<Option<OutputLimit>> = RwLock::new(None);
+
+pub fn set_output_limit(limit: OutputLimit) {
+    if let Ok(mut current) = LIMIT.write() {
+        *current = Some(limit);
+    }
+}
+
+pub fn output_limit() -> OutputLimit {
+    LIMIT
+        .read()
+        .ok()
+        .and_then(|l| l.clone())
+        .unwrap_or_default()
+}
+
+/// Serialize a response.
+///
+/// When it exceeds `limit.max_bytes`, `data` is written to
+/// `<spill_dir>/bt-output-<file_stem(trace_id)>.json` and the response carries
+/// `data_path` instead.
+pub fn render<T: Serialize>(
+    response: ToolResponse<T>,
+    limit: &OutputLimit,
+) -> Result<String, BtError> {
+    let json = serde_json::to_string(&response)?;
+    if json.len() <= limit.max_bytes || response.data.is_none() {
+        return Ok(json);
+    }
+
+    let path = limit
+        .spill_dir
+        .join(format!("bt-output-{}.json", file_stem(&response.trace_id)));
+    let data = serde_json::to_string(&response.data)?;
+    std::fs::write(&path, &data).map_err(|e| {
+        BtError::Io(format!(
+            "Failed to spill output to {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+
+    let spilled: ToolResponse<()> = ToolResponse {
+        success: response.success,
+        data: None,
+        error: response.error,
+        data_path: Some(path.display().to_string()),
+        usage: response.usage,
+        trace_id: response.trace_id,
+        duration_ms: response.duration_ms,
+    };
+    Ok(serde_json::to_string(&spilled)?)
+}
+
+/// `id` with everything outside `[A-Za-z0-9_-]` replaced by `_`, so a caller-supplied
+/// trace id can name a file without escaping its directory
+pub fn file_stem(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_large_data_spills_to_file() {
+        let limit = OutputLimit {
+            max_bytes: 128,
+            spill_dir: std::env::temp_dir(),
+        };
+        let small = render(ToolResponse::ok("ok", "t-small".to_string(), 1.0), &limit).unwrap();
+        assert!(small.contains("\"data\":\"ok\""));
+
+        let big = "x".repeat(200);
+        let json = render(ToolResponse::ok(&big, "t-spill".to_string(), 1.0), &limit).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value.get("data").is_none());
+        let path = value["data_path"].as_str().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            format!("\"{}\"", big)
+        );
+        std::fs::remove_file(path).unwrap();
+
+        let json = render(ToolResponse::ok(&big, "../../x".to_string(), 1.0), &limit).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let path = Path::new(value["data_path"].as_str().unwrap());
+        assert_eq!(path, std::env::temp_dir().join("bt-output-______x.json"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+// Child processes with a deadline, for tools that shell out to checkers and CLIs
+
+use std::io::{self, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Output, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Run `command` with stdin closed and both output streams captured.
+///
+/// The child leads its own process group, and the whole group is killed if it
+/// outlives `timeout`, so helpers it spawned (cargo's rustc, opencode's
+/// server) go down with it. Returns `Ok(None)` on timeout and an error when
+/// the program could not be started.
+pub fn output_with_timeout(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> io::Result<Option<Output>> {
+    if timeout == Some(Duration::ZERO) {
+        return Ok(None);
+    }
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()?;
+    // Drain both pipes on threads so a chatty child cannot block on a full pipe
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait()? {
+            Some(status) => break status,
+            None if timeout.is_some_and(|t| started.elapsed() >= t) => {
+                // SAFETY: kill(2) with a negative pid signals the group the child leads
+                unsafe {
+                    libc::kill(-(child.id() as i32), libc::SIGKILL);
+                }
+                let _ = child.wait();
+                return Ok(None);
+            }
+            None => std::thread::sleep(Duration::from_millis(20)),
+        }
+    };
+
+    Ok(Some(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout_kills_process_group() {
+        let pid_file = std::env::temp_dir().join(format!("bt-core-group-{}", std::process::id()));
+        let script = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let started = Instant::now();
+        let output = output_with_timeout(
+            Command::new("sh").args(["-c", &script]),
+            Some(Duration::from_millis(300)),
+        );
+        assert!(output.unwrap().is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // The grandchild sleep went down with the group; a zombie counts as gone
+        let pid = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .to_string();
+        std::fs::remove_file(&pid_file).unwrap();
+        let alive = || {
+            std::fs::read_to_string(format!("/proc/{}/stat", pid)).is_ok_and(|stat| {
+                stat.rsplit(") ")
+                    .next()
+                    .is_some_and(|rest| !rest.starts_with('Z'))
+            })
+        };
+        let gone_by = Instant::now() + Duration::from_secs(2);
+        while alive() && Instant::now() < gone_by {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(!alive());
+    }
+
+    #[test]
+    fn test_output_is_captured() {
+        let output = output_with_timeout(
+            Command::new("sh").args(["-c", "echo hi; echo oops >&2"]),
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            (output.stdout.as_slice(), output.stderr.as_slice()),
+            (&b"hi\n"[..], &b"oops\n"[..])
+        );
+        assert!(output_with_timeout(&mut Command::new("/nonexistent/tool"), None).is_err());
+        assert!(
+            output_with_timeout(&mut Command::new("true"), Some(Duration::ZERO))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
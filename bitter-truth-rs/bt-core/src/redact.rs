@@ -0,0 +1,116 @@
+// Masking of credentials before they reach stderr
+
+use regex::Regex;
+use std::sync::RwLock;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Key fragments that always mark a field as sensitive
+const DEFAULT_KEYS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "authorization",
+];
+
+static EXTRA_KEYS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Register additional sensitive key fragments (case-insensitive)
+pub fn add_sensitive_keys<I: IntoIterator<Item = String>>(keys: I) {
+    if let Ok(mut extra) = EXTRA_KEYS.write() {
+        for key in keys {
+            let key = key.trim().to_ascii_lowercase();
+            if !key.is_empty() && !extra.contains(&key) {
+                extra.push(key);
+            }
+        }
+    }
+}
+
+fn sensitive_keys() -> Vec<String> {
+    let mut keys: Vec<String> = DEFAULT_KEYS.iter().map(|k| k.to_string()).collect();
+    if let Ok(extra) = EXTRA_KEYS.read() {
+        keys.extend(extra.iter().cloned());
+    }
+    keys
+}
+
+/// Whether a field name ends with a sensitive fragment, e.g. `github_token`
+/// or `accessToken`; counters like `prompt_tokens` are left alone
+pub fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase().replace('-', "_");
+    sensitive_keys().iter().any(|k| key.ends_with(k.as_str()))
+}
+
+/// Mask sensitive fields, recursing into objects and arrays
+pub fn redact_value(key: &str, value: serde_json::Value) -> serde_json::Value {
+    if is_sensitive_key(key) {
+        return serde_json::Value::String(REDACTED.to_string());
+    }
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .map(|(k, v)| {
+                    let v = redact_value(&k, v);
+                    (k, v)
+                })
+                .collect(),
+        ),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(|v| redact_value("", v)).collect())
+        }
+        serde_json::Value::String(s) => serde_json::Value::String(redact_text(&s)),
+        other => other,
+    }
+}
+
+/// Mask `key=value`, `key: value`, `"key": "value"` and bearer tokens in free text
+pub fn redact_text(text: &str) -> String {
+    let keys = sensitive_keys()
+        .iter()
+        .map(|k| regex::escape(k))
+        .collect::<Vec<_>>()
+        .join("|");
+    let pairs = format!(r#"(?i)(\w*(?:{})"?\s*[:=]\s*"?)([^\s",;}}]+)"#, keys);
+
+    let mut out = match Regex::new(r"(?i)(bearer\s+)[A-Za-z0-9._~+/=-]+") {
+        Ok(re) => re
+            .replace_all(text, format!("${{1}}{}", REDACTED))
+            .into_owned(),
+        Err(_) => text.to_string(),
+    };
+    if let Ok(re) = Regex::new(&pairs) {
+        out = re
+            .replace_all(&out, format!("${{1}}{}", REDACTED))
+            .into_owned();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_text_pairs_and_bearer() {
+        let text = r#"calling with api_key=sk-123 and {"password": "hunter2"}, Authorization: Bearer abc.def"#;
+        let out = redact_text(text);
+        assert!(!out.contains("sk-123"));
+        assert!(!out.contains("hunter2"));
+        assert!(!out.contains("abc.def"));
+        assert!(out.contains("api_key=[REDACTED]"));
+    }
+
+    #[test]
+    fn test_redact_value_nested() {
+        let value = serde_json::json!({"user": "lewis", "auth": {"github_token": "ghp_x"}});
+        let out = redact_value("input", value);
+        assert_eq!(out["user"], "lewis");
+        assert_eq!(out["auth"]["github_token"], REDACTED);
+        assert_eq!(redact_value("password", serde_json::json!("x")), REDACTED);
+        assert_eq!(redact_value("prompt_tokens", serde_json::json!(12)), 12);
+    }
+}
@@ -0,0 +1,133 @@
+// Retry policy shared by tool inputs and the self-healing feedback loop
+
+use crate::ErrorCode;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::time::Duration;
+
+/// Delay schedule between attempts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Backoff {
+    Fixed {
+        delay_ms: u64,
+    },
+    Exponential {
+        initial_ms: u64,
+        max_ms: u64,
+        multiplier: f64,
+    },
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff::Exponential {
+            initial_ms: 1000,
+            max_ms: 30_000,
+            multiplier: 2.0,
+        }
+    }
+}
+
+/// How many times to try, how long to wait, and which failures qualify
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub backoff: Backoff,
+    /// Randomize each delay between 50% and 100% of its nominal value
+    pub jitter: bool,
+    pub retry_on: Vec<ErrorCode>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            backoff: Backoff::default(),
+            jitter: true,
+            retry_on: vec![
+                ErrorCode::ContractViolation,
+                ErrorCode::GateFailed,
+                ErrorCode::Timeout,
+                ErrorCode::Io,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether a failure with `code` on 1-based `attempt` warrants another try
+    pub fn should_retry(&self, attempt: u32, code: ErrorCode) -> bool {
+        attempt < self.max_attempts && self.retry_on.contains(&code)
+    }
+
+    /// Delay to wait after the given 1-based `attempt` failed
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let nominal = match self.backoff {
+            Backoff::Fixed { delay_ms } => delay_ms,
+            Backoff::Exponential {
+                initial_ms,
+                max_ms,
+                multiplier,
+            } => {
+                let exp = multiplier.powi(attempt.saturating_sub(1) as i32);
+                ((initial_ms as f64) * exp).min(max_ms as f64) as u64
+            }
+        };
+        let ms = if self.jitter {
+            let r = (uuid::Uuid::new_v4().as_u128() % 1000) as u64;
+            nominal / 2 + nominal / 2 * r / 1000
+        } else {
+            nominal
+        };
+        Duration::from_millis(ms)
+    }
+
+    /// Human-readable "n/max" label used in prompts and logs
+    pub fn attempt_label(&self, attempt: u32) -> String {
+        format!("{}/{}", attempt, self.max_attempts)
+    }
+}
+
+/// Accepts an attempt number or a legacy `"n/max"` string
+pub fn deserialize_attempt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Number(u32),
+        Label(String),
+    }
+
+    match Raw::deserialize(deserializer)? {
+        Raw::Number(n) => Ok(n),
+        Raw::Label(s) => s
+            .split('/')
+            .next()
+            .and_then(|n| n.trim().parse().ok())
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid attempt: {}", s))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exponential_delay_is_capped() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.delay_for(1), Duration::from_millis(1000));
+        assert_eq!(policy.delay_for(3), Duration::from_millis(4000));
+        assert_eq!(policy.delay_for(10), Duration::from_millis(30_000));
+    }
+
+    #[test]
+    fn test_should_retry_respects_codes_and_budget() {
+        let policy = RetryPolicy::default();
+        assert!(policy.should_retry(1, ErrorCode::GateFailed));
+        assert!(!policy.should_retry(5, ErrorCode::GateFailed));
+        assert!(!policy.should_retry(1, ErrorCode::InvalidInput));
+    }
+}
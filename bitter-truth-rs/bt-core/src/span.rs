@@ -0,0 +1,86 @@
+// Scoped phase timers that log begin/end events to stderr
+
+use crate::{elapsed_ms, log_stderr, LogEntry};
+use std::cell::RefCell;
+use std::time::SystemTime;
+
+thread_local! {
+    static STACK: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A timed phase; logs `span begin` on enter and `span end` with duration on drop.
+///
+/// Spans entered while another is active on the same thread nest under it,
+/// e.g. `generate/extract`.
+pub struct Span {
+    path: String,
+    trace_id: String,
+    start: SystemTime,
+}
+
+impl Span {
+    pub fn enter(name: &str, trace_id: &str) -> Self {
+        let path = STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            let path = match stack.last() {
+                Some(parent) => format!("{}/{}", parent, name),
+                None => name.to_string(),
+            };
+            stack.push(path.clone());
+            path
+        });
+
+        let log = LogEntry::debug("span begin", trace_id.to_string())
+            .with_extra("span", serde_json::Value::String(path.clone()));
+        log_stderr(&log);
+
+        Self {
+            path,
+            trace_id: trace_id.to_string(),
+            start: SystemTime::now(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn elapsed_ms(&self) -> f64 {
+        elapsed_ms(self.start)
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            if let Some(pos) = stack.iter().rposition(|p| p == &self.path) {
+                stack.remove(pos);
+            }
+        });
+
+        let log = LogEntry::info("span end", self.trace_id.clone())
+            .with_extra("span", serde_json::Value::String(self.path.clone()))
+            .with_extra("duration_ms", serde_json::json!(self.elapsed_ms()));
+        log_stderr(&log);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_spans_build_paths() {
+        let outer = Span::enter("generate", "t1");
+        {
+            let inner = Span::enter("extract", "t1");
+            assert_eq!(inner.path(), "generate/extract");
+        }
+        let sibling = Span::enter("write", "t1");
+        assert_eq!(sibling.path(), "generate/write");
+        drop(sibling);
+        drop(outer);
+        assert_eq!(Span::enter("next", "t1").path(), "next");
+    }
+}
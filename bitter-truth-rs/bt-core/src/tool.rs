@@ -0,0 +1,160 @@
+// Return-based tool runner: JSON input in, ToolResponse out
+
+use crate::{
+    elapsed_ms, input, log_stderr, output, usage, BtError, Context, LogEntry, ToolConfig,
+    ToolError, ToolResponse,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::process::ExitCode;
+use std::time::SystemTime;
+
+/// Read the input (stdin, `--input <file>` or `BT_INPUT_PATH`), run `tool`
+/// and print its response.
+///
+/// Returns the exit code instead of exiting, so `main` can end with
+/// `bt_core::run(tool)`.
+pub fn run<I, O, F>(tool: F) -> ExitCode
+where
+    I: DeserializeOwned,
+    O: Serialize,
+    F: FnOnce(I) -> Result<O, ToolError>,
+{
+    let start = SystemTime::now();
+    let raw = match input::read_input() {
+        Ok(raw) => raw,
+        Err(e) => {
+            let response: ToolResponse<()> = fail(e.into(), "unknown".to_string(), start);
+            return ExitCode::from(print_response(response) as u8);
+        }
+    };
+
+    let response = execute(&raw, tool);
+    ExitCode::from(print_response(response) as u8)
+}
+
+/// Parse `raw`, fill its context from `ToolConfig`, and run `tool`.
+///
+/// Nothing is read from stdin or printed to stdout, which makes this the
+/// entry point for unit tests.
+pub fn execute<I, O, F>(raw: &str, tool: F) -> ToolResponse<O>
+where
+    I: DeserializeOwned,
+    F: FnOnce(I) -> Result<O, ToolError>,
+{
+    let start = SystemTime::now();
+    let (input, trace_id) = match parse_input::<I>(raw, ToolConfig::load) {
+        Ok(parsed) => parsed,
+        Err((error, trace_id)) => return fail(error, trace_id, start),
+    };
+
+    let mut response = match tool(input) {
+        Ok(data) => ToolResponse::ok(data, trace_id, elapsed_ms(start)),
+        Err(error) => fail(error, trace_id, start),
+    };
+    response.usage = usage::take();
+    response
+}
+
+fn fail<O>(error: ToolError, trace_id: String, start: SystemTime) -> ToolResponse<O> {
+    let log = LogEntry::error(error.message.clone(), trace_id.clone())
+        .with_extra("code", serde_json::json!(error.code));
+    log_stderr(&log);
+    ToolResponse::failure(error, trace_id, elapsed_ms(start))
+}
+
+/// Parse `raw` and fill its context from the config `load` returns
+fn parse_input<I: DeserializeOwned>(
+    raw: &str,
+    load: impl FnOnce() -> Result<ToolConfig, BtError>,
+) -> Result<(I, String), (ToolError, String)> {
+    let unknown = || "unknown".to_string();
+    let mut value: serde_json::Value =
+        serde_json::from_str(raw).map_err(|e| (BtError::from(e).into(), unknown()))?;
+
+    let mut context: Context = match value.get("context") {
+        Some(c) => {
+            serde_json::from_value(c.clone()).map_err(|e| (BtError::from(e).into(), unknown()))?
+        }
+        None => Context::default(),
+    };
+    let trace_id = context.trace_id.clone();
+
+    let config = load().map_err(|e| (e.into(), trace_id.clone()))?;
+    config.apply_to(&mut context);
+
+    if let Some(obj) = value.as_object_mut() {
+        let context = serde_json::to_value(&context)
+            .map_err(|e| (BtError::from(e).into(), trace_id.clone()))?;
+        obj.insert("context".to_string(), context);
+    }
+
+    let input =
+        serde_json::from_value(value).map_err(|e| (BtError::from(e).into(), trace_id.clone()))?;
+    Ok((input, trace_id))
+}
+
+/// Print a response on stdout and return the matching exit code
+pub(crate) fn print_response<T: Serialize>(response: ToolResponse<T>) -> i32 {
+    let success = response.success;
+    let trace_id = response.trace_id.clone();
+    let duration_ms = response.duration_ms;
+    match output::render(response, &output::output_limit()) {
+        Ok(json) => {
+            println!("{}", json);
+            if success {
+                0
+            } else {
+                1
+            }
+        }
+        Err(e) => {
+            let response: ToolResponse<()> = ToolResponse::failure(e.into(), trace_id, duration_ms);
+            println!("{}", serde_json::to_string(&response).unwrap());
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct EchoInput {
+        message: String,
+        #[serde(default)]
+        context: Context,
+    }
+
+    #[test]
+    fn test_execute_returns_data_with_input_trace_id() {
+        let raw = r#"{"message": "hi", "context": {"trace_id": "abc", "dry_run": false}}"#;
+        let response = execute(raw, |input: EchoInput| {
+            assert!(input.context.timeout_seconds.is_some());
+            Ok(input.message)
+        });
+        assert!(response.success);
+        assert_eq!(response.data.as_deref(), Some("hi"));
+        assert_eq!(response.trace_id, "abc");
+    }
+
+    #[test]
+    fn test_missing_context_takes_timeout_from_env() {
+        let load = || {
+            let mut config = ToolConfig::default();
+            config.apply_env(|k| (k == "BT_TIMEOUT_SECONDS").then(|| "42".to_string()))?;
+            Ok(config)
+        };
+        let (input, _) = parse_input::<EchoInput>(r#"{"message": "hi"}"#, load).unwrap();
+        assert_eq!(input.context.timeout_seconds, Some(42));
+    }
+
+    #[test]
+    fn test_execute_reports_invalid_input() {
+        let response = execute("{\"nope\": 1}", |input: EchoInput| Ok(input.message));
+        assert!(!response.success);
+        assert_eq!(response.error.unwrap().code, crate::ErrorCode::InvalidInput);
+    }
+}
@@ -0,0 +1,57 @@
+// W3C trace context (traceparent/tracestate) carried into log entries
+
+use std::sync::RwLock;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TraceContext {
+    pub traceparent: Option<String>,
+    pub tracestate: Option<String>,
+}
+
+static CURRENT: RwLock<TraceContext> = RwLock::new(TraceContext {
+    traceparent: None,
+    tracestate: None,
+});
+
+/// Install the trace context attached to every subsequent `LogEntry`
+pub fn set_trace_context(trace: TraceContext) {
+    if let Ok(mut current) = CURRENT.write() {
+        *current = trace;
+    }
+}
+
+pub fn trace_context() -> TraceContext {
+    CURRENT.read().map(|c| c.clone()).unwrap_or_default()
+}
+
+/// Check the `version-traceid-parentid-flags` shape, e.g.
+/// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`
+pub fn is_valid_traceparent(value: &str) -> bool {
+    let parts: Vec<&str> = value.trim().split('-').collect();
+    let hex = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_hexdigit());
+    parts.len() == 4
+        && hex(parts[0], 2)
+        && parts[0] != "ff"
+        && hex(parts[1], 32)
+        && parts[1].bytes().any(|b| b != b'0')
+        && hex(parts[2], 16)
+        && parts[2].bytes().any(|b| b != b'0')
+        && hex(parts[3], 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_validation() {
+        assert!(is_valid_traceparent(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        ));
+        assert!(!is_valid_traceparent(
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01"
+        ));
+        assert!(!is_valid_traceparent("00-4bf92f35-00f067aa0ba902b7-01"));
+        assert!(!is_valid_traceparent("garbage"));
+    }
+}
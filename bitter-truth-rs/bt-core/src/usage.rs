@@ -0,0 +1,83 @@
+// LLM token and cost accounting reported in the response envelope
+
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl Usage {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_tokens() == 0 && self.cost_usd == 0.0
+    }
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+impl std::iter::Sum for Usage {
+    fn sum<I: Iterator<Item = Usage>>(iter: I) -> Self {
+        iter.fold(Usage::default(), |mut acc, u| {
+            acc += u;
+            acc
+        })
+    }
+}
+
+static TOTAL: Mutex<Usage> = Mutex::new(Usage {
+    prompt_tokens: 0,
+    completion_tokens: 0,
+    cost_usd: 0.0,
+});
+
+/// Add usage from one model call to this run's total
+pub fn record(usage: Usage) {
+    if let Ok(mut total) = TOTAL.lock() {
+        *total += usage;
+    }
+}
+
+/// Drain the accumulated total; `None` when nothing was recorded
+pub fn take() -> Option<Usage> {
+    let total = TOTAL.lock().map(|mut t| std::mem::take(&mut *t)).ok()?;
+    (!total.is_empty()).then_some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_sums() {
+        let total: Usage = [
+            Usage {
+                prompt_tokens: 10,
+                completion_tokens: 5,
+                cost_usd: 0.01,
+            },
+            Usage {
+                prompt_tokens: 1,
+                completion_tokens: 2,
+                cost_usd: 0.02,
+            },
+        ]
+        .into_iter()
+        .sum();
+        assert_eq!(total.total_tokens(), 18);
+        assert!((total.cost_usd - 0.03).abs() < 1e-9);
+    }
+}
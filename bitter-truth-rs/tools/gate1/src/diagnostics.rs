@@ -0,0 +1,456 @@
+// Structured diagnostics parsed from compiler and linter output
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn parse(s: &str) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "error" | "fatal" | "error: internal compiler error" => Severity::Error,
+            "warning" | "warn" => Severity::Warning,
+            _ => Severity::Info,
+        }
+    }
+}
+
+/// Lowest diagnostic severity that fails the gate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailOn {
+    /// Failed checks fail the gate; warnings are only reported
+    #[default]
+    Error,
+    /// Any warning fails the gate too
+    Warning,
+    /// Never fail; everything is reported for feedback only
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: u32,
+    pub col: u32,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(
+        file: &str,
+        line: u32,
+        col: u32,
+        severity: Severity,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            file: file.to_string(),
+            line,
+            col,
+            severity,
+            message: message.into(),
+            code: None,
+        }
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = serde_json::to_value(self.severity).ok();
+        let severity = severity
+            .as_ref()
+            .and_then(|s| s.as_str())
+            .unwrap_or("error");
+        write!(f, "{}:{}:{}: {}", self.file, self.line, self.col, severity)?;
+        if let Some(code) = &self.code {
+            write!(f, "[{}]", code)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// rustc `--error-format=json` lines, or cargo `--message-format=json` lines
+pub fn parse_rustc_json(output: &str) -> Vec<Diagnostic> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter_map(|v| {
+            let msg = if v["reason"] == "compiler-message" {
+                v["message"].clone()
+            } else {
+                v
+            };
+            msg.get("level")?;
+            let severity = Severity::parse(msg["level"].as_str().unwrap_or(""));
+            // Summaries like "aborting due to N previous errors" have no spans
+            let spans = msg["spans"].as_array()?;
+            let span = spans
+                .iter()
+                .find(|s| s["is_primary"] == true)
+                .or(spans.first())?;
+            let mut d = Diagnostic::new(
+                span["file_name"].as_str().unwrap_or(""),
+                span["line_start"].as_u64().unwrap_or(0) as u32,
+                span["column_start"].as_u64().unwrap_or(0) as u32,
+                severity,
+                msg["message"].as_str().unwrap_or(""),
+            );
+            if let Some(code) = msg["code"]["code"].as_str() {
+                d = d.with_code(code);
+            }
+            Some(d)
+        })
+        .collect()
+}
+
+/// `tsc --pretty false`: `file.ts(3,5): error TS2322: message`
+pub fn parse_tsc(output: &str) -> Vec<Diagnostic> {
+    let re = Regex::new(r"^(.+?)\((\d+),(\d+)\): (error|warning) (TS\d+): (.*)$").unwrap();
+    output
+        .lines()
+        .filter_map(|line| re.captures(line.trim_end()))
+        .map(|c| {
+            Diagnostic::new(
+                &c[1],
+                c[2].parse().unwrap_or(0),
+                c[3].parse().unwrap_or(0),
+                Severity::parse(&c[4]),
+                &c[6],
+            )
+            .with_code(&c[5])
+        })
+        .collect()
+}
+
+/// mypy with `--show-column-numbers --show-error-codes`:
+/// `file.py:3:5: error: message  [code]`
+pub fn parse_mypy(output: &str) -> Vec<Diagnostic> {
+    let re =
+        Regex::new(r"^(.+?):(\d+):(?:(\d+):)? (error|warning|note): (.*?)(?:\s+\[([\w-]+)\])?$")
+            .unwrap();
+    output
+        .lines()
+        .filter_map(|line| re.captures(line.trim_end()))
+        .map(|c| {
+            let col = c.get(3).and_then(|m| m.as_str().parse().ok()).unwrap_or(0);
+            let d = Diagnostic::new(
+                &c[1],
+                c[2].parse().unwrap_or(0),
+                col,
+                Severity::parse(&c[4]),
+                &c[5],
+            );
+            match c.get(6) {
+                Some(code) => d.with_code(code.as_str()),
+                None => d,
+            }
+        })
+        .collect()
+}
+
+/// `ruff check --output-format json`
+pub fn parse_ruff_json(output: &str) -> Vec<Diagnostic> {
+    let Some(parsed) = first_json(output) else {
+        return vec![];
+    };
+    parsed
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|f| {
+            // Ruff reports syntax errors with a null code
+            let severity = if f["code"].is_null() {
+                Severity::Error
+            } else {
+                Severity::Warning
+            };
+            let d = Diagnostic::new(
+                f["filename"].as_str().unwrap_or(""),
+                f["location"]["row"].as_u64().unwrap_or(0) as u32,
+                f["location"]["column"].as_u64().unwrap_or(0) as u32,
+                severity,
+                f["message"].as_str().unwrap_or(""),
+            );
+            match f["code"].as_str() {
+                Some(code) => d.with_code(code),
+                None => d,
+            }
+        })
+        .collect()
+}
+
+/// `python3 -m py_compile` traceback: `File "x.py", line 3` ... `SyntaxError: msg`
+pub fn parse_py_compile(output: &str) -> Vec<Diagnostic> {
+    let loc = Regex::new(r#"File "(.+?)", line (\d+)"#).unwrap();
+    let err = Regex::new(r"^\s*(\w*Error): (.*)$").unwrap();
+    let Some(at) = loc.captures_iter(output).last() else {
+        return vec![];
+    };
+    let Some(e) = output.lines().rev().find_map(|l| err.captures(l)) else {
+        return vec![];
+    };
+    vec![Diagnostic::new(
+        &at[1],
+        at[2].parse().unwrap_or(0),
+        0,
+        Severity::Error,
+        &e[2],
+    )
+    .with_code(&e[1])]
+}
+
+/// Generic `file:line:col: message` output (gofmt)
+pub fn parse_colon_format(output: &str) -> Vec<Diagnostic> {
+    let re = Regex::new(r"^(.+?):(\d+):(\d+): (.*)$").unwrap();
+    output
+        .lines()
+        .filter_map(|line| re.captures(line.trim_end()))
+        .map(|c| {
+            Diagnostic::new(
+                &c[1],
+                c[2].parse().unwrap_or(0),
+                c[3].parse().unwrap_or(0),
+                Severity::Error,
+                &c[4],
+            )
+        })
+        .collect()
+}
+
+/// `go vet`: its analyzer findings are warnings, left to `lint_level`/`fail_on`; a
+/// package that fails to type-check is reported as `vet: file:line:col: ...` and is an error
+pub fn parse_go_vet(output: &str) -> Vec<Diagnostic> {
+    let re = Regex::new(r"^(vet: )?(.+?):(\d+):(\d+): (.*)$").unwrap();
+    output
+        .lines()
+        .filter_map(|line| re.captures(line.trim_end()))
+        .map(|c| {
+            let severity = if c.get(1).is_some() {
+                Severity::Error
+            } else {
+                Severity::Warning
+            };
+            Diagnostic::new(
+                &c[2],
+                c[3].parse().unwrap_or(0),
+                c[4].parse().unwrap_or(0),
+                severity,
+                &c[5],
+            )
+        })
+        .collect()
+}
+
+/// `nu-check --debug`: one miette report per error, such as
+/// `Error: nu::parser::unclosed_delimiter`, then `× message` and `╭─[file:line:col]`
+pub fn parse_nu_check(output: &str) -> Vec<Diagnostic> {
+    let at = Regex::new(r"╭─\[(.+):(\d+):(\d+)\]").unwrap();
+    let mut found: Vec<Diagnostic> = vec![];
+    for line in output.lines().map(str::trim) {
+        if let Some(code) = line.strip_prefix("Error: ") {
+            found.push(Diagnostic::new("", 0, 0, Severity::Error, "").with_code(code.trim()));
+            continue;
+        }
+        let Some(current) = found.last_mut() else {
+            continue;
+        };
+        if let Some(message) = line.strip_prefix("× ") {
+            if current.message.is_empty() {
+                current.message = message.trim().to_string();
+            }
+        } else if let Some(c) = at.captures(line).filter(|_| current.file.is_empty()) {
+            current.file = c[1].to_string();
+            current.line = c[2].parse().unwrap_or(0);
+            current.col = c[3].parse().unwrap_or(0);
+        }
+    }
+    found
+}
+
+/// `shellcheck --format=json1`
+pub fn parse_shellcheck(output: &str) -> Vec<Diagnostic> {
+    let Some(parsed) = first_json(output) else {
+        return vec![];
+    };
+    parsed["comments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|c| {
+            Diagnostic::new(
+                c["file"].as_str().unwrap_or(""),
+                c["line"].as_u64().unwrap_or(0) as u32,
+                c["column"].as_u64().unwrap_or(0) as u32,
+                Severity::parse(c["level"].as_str().unwrap_or("error")),
+                c["message"].as_str().unwrap_or(""),
+            )
+            .with_code(format!("SC{}", c["code"].as_u64().unwrap_or(0)))
+        })
+        .collect()
+}
+
+/// `eslint --format json`
+pub fn parse_eslint(output: &str) -> Vec<Diagnostic> {
+    let Some(parsed) = first_json(output) else {
+        return vec![];
+    };
+    parsed
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|file| {
+            let path = file["filePath"].as_str().unwrap_or("").to_string();
+            file["messages"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |m| {
+                    let severity = if m["severity"].as_u64() == Some(2) {
+                        Severity::Error
+                    } else {
+                        Severity::Warning
+                    };
+                    let d = Diagnostic::new(
+                        &path,
+                        m["line"].as_u64().unwrap_or(0) as u32,
+                        m["column"].as_u64().unwrap_or(0) as u32,
+                        severity,
+                        m["message"].as_str().unwrap_or(""),
+                    );
+                    match m["ruleId"].as_str() {
+                        Some(rule) => d.with_code(rule),
+                        None => d,
+                    }
+                })
+        })
+        .collect()
+}
+
+/// The first JSON document in mixed stdout/stderr text
+fn first_json(output: &str) -> Option<serde_json::Value> {
+    let start = output.find(['{', '['])?;
+    serde_json::Deserializer::from_str(&output[start..])
+        .into_iter::<serde_json::Value>()
+        .next()?
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rustc_json() {
+        let line = r#"{"$message_type":"diagnostic","message":"mismatched types","code":{"code":"E0308","explanation":null},"level":"error","spans":[{"file_name":"src/main.rs","line_start":4,"column_start":18,"is_primary":true}],"children":[],"rendered":""}
+{"$message_type":"diagnostic","message":"aborting due to 1 previous error","code":null,"level":"error","spans":[],"children":[],"rendered":""}"#;
+        let diags = parse_rustc_json(line);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(
+            diags[0].to_string(),
+            "src/main.rs:4:18: error[E0308]: mismatched types"
+        );
+    }
+
+    #[test]
+    fn test_parse_tsc_and_mypy() {
+        let tsc =
+            parse_tsc("a.ts(3,5): error TS2322: Type 'string' is not assignable to type 'number'.");
+        assert_eq!(tsc[0].code.as_deref(), Some("TS2322"));
+        assert_eq!((tsc[0].line, tsc[0].col), (3, 5));
+
+        let mypy = parse_mypy("a.py:7:12: error: Incompatible return value type  [return-value]");
+        assert_eq!(mypy[0].code.as_deref(), Some("return-value"));
+        assert_eq!(mypy[0].message, "Incompatible return value type");
+    }
+
+    #[test]
+    fn test_parse_py_compile() {
+        let out =
+            "  File \"/tmp/x.py\", line 2\n    def (\n        ^\nSyntaxError: invalid syntax\n";
+        let diags = parse_py_compile(out);
+        assert_eq!(diags[0].line, 2);
+        assert_eq!(diags[0].code.as_deref(), Some("SyntaxError"));
+    }
+
+    #[test]
+    fn test_parse_nu_check() {
+        let out = "Error: nu::parser::unclosed_delimiter\n\n  × Unclosed delimiter.\n   ╭─[/tmp/x.nu:2:13]\n 1 │ def main [] {\n 2 │     print \"hi\"\n   ·             ─┬─\n   ·              ╰── unclosed {\n   ╰────\n";
+        let diags = parse_nu_check(out);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(
+            diags[0].to_string(),
+            "/tmp/x.nu:2:13: error[nu::parser::unclosed_delimiter]: Unclosed delimiter."
+        );
+        assert!(parse_nu_check("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_shellcheck() {
+        let out = r#"{"comments":[{"file":"run.sh","line":4,"endLine":4,"column":6,"endColumn":10,"level":"warning","code":2086,"message":"Double quote to prevent globbing and word splitting."},{"file":"run.sh","line":9,"endLine":9,"column":1,"endColumn":3,"level":"error","code":1073,"message":"Couldn't parse this if expression."}]}"#;
+        let diags = parse_shellcheck(out);
+        assert_eq!(diags.len(), 2);
+        assert_eq!(
+            diags[0].to_string(),
+            "run.sh:4:6: warning[SC2086]: Double quote to prevent globbing and word splitting."
+        );
+        assert_eq!(diags[1].severity, Severity::Error);
+        assert_eq!(diags[1].code.as_deref(), Some("SC1073"));
+        assert!(parse_shellcheck("shellcheck: command not found").is_empty());
+    }
+
+    #[test]
+    fn test_parse_eslint_and_tsc_project() {
+        let out = r#"[{"filePath":"/src/app.js","messages":[{"ruleId":"no-unused-vars","severity":1,"message":"'x' is assigned a value but never used.","line":2,"column":7},{"ruleId":null,"fatal":true,"severity":2,"message":"Parsing error: Unexpected token )","line":5,"column":12}],"errorCount":1,"warningCount":1}]"#;
+        let diags = parse_eslint(out);
+        assert_eq!(diags.len(), 2);
+        assert_eq!(
+            diags[0].to_string(),
+            "/src/app.js:2:7: warning[no-unused-vars]: 'x' is assigned a value but never used."
+        );
+        assert_eq!(
+            (diags[1].severity, diags[1].code.as_deref()),
+            (Severity::Error, None)
+        );
+        assert!(parse_eslint("[]").is_empty());
+
+        // `tsc -p` prefixes project-relative paths and mixes in summary lines
+        let tsc = parse_tsc("src/index.ts(10,3): error TS2304: Cannot find name 'foo'.\n\nFound 1 error in src/index.ts:10\n");
+        assert_eq!(tsc.len(), 1);
+        assert_eq!(
+            tsc[0].to_string(),
+            "src/index.ts:10:3: error[TS2304]: Cannot find name 'foo'."
+        );
+    }
+
+    #[test]
+    fn test_parse_go_vet() {
+        let out = "# example\n./main.go:6:2: fmt.Printf format %d has arg \"x\" of wrong type string\nvet: ./util.go:3:9: undefined: helper\n";
+        let diags = parse_go_vet(out);
+        assert_eq!(diags.len(), 2);
+        assert_eq!(
+            (diags[0].file.as_str(), diags[0].severity),
+            ("./main.go", Severity::Warning)
+        );
+        assert_eq!(
+            (diags[1].file.as_str(), diags[1].severity),
+            ("./util.go", Severity::Error)
+        );
+    }
+}
@@ -0,0 +1,116 @@
+// Lint policy: which linter findings fail the gate
+
+use crate::diagnostics::{Diagnostic, Severity};
+use bt_core::BtError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// Only error-level findings and denied codes fail the gate
+    #[default]
+    Warn,
+    /// Any warning or error finding fails the gate
+    Deny,
+}
+
+impl LintLevel {
+    pub fn parse(s: &str) -> Result<Self, BtError> {
+        match s.to_ascii_lowercase().as_str() {
+            "warn" => Ok(LintLevel::Warn),
+            "deny" => Ok(LintLevel::Deny),
+            other => Err(BtError::InvalidInput(format!(
+                "Invalid lint level: {} (expected warn or deny)",
+                other
+            ))),
+        }
+    }
+}
+
+/// `[gate1.lint]`: level plus code lists, e.g. `deny = ["clippy::unwrap_used"]`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LintPolicy {
+    pub level: LintLevel,
+    /// Codes that fail the gate even at `warn`
+    pub deny: Vec<String>,
+    /// Codes dropped from the report entirely
+    pub allow: Vec<String>,
+}
+
+impl LintPolicy {
+    /// Drop allowed findings and decide whether the rest pass
+    pub fn apply(&self, findings: Vec<Diagnostic>) -> (bool, Vec<Diagnostic>) {
+        let listed =
+            |list: &[String], d: &Diagnostic| d.code.as_ref().is_some_and(|c| list.contains(c));
+        let findings: Vec<Diagnostic> = findings
+            .into_iter()
+            .filter(|d| !listed(&self.allow, d))
+            .collect();
+        let ok = !findings.iter().any(|d| {
+            d.severity == Severity::Error
+                || (d.severity == Severity::Warning && self.level == LintLevel::Deny)
+                || listed(&self.deny, d)
+        });
+        (ok, findings)
+    }
+}
+
+/// `--lint-level <warn|deny>` (or `--lint-level=<...>`) from the command line
+pub fn level_from_args<I: IntoIterator<Item = String>>(
+    args: I,
+) -> Result<Option<LintLevel>, BtError> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--lint-level" {
+            let value = args.next().ok_or_else(|| {
+                BtError::InvalidInput("--lint-level requires a value".to_string())
+            })?;
+            return LintLevel::parse(&value).map(Some);
+        }
+        if let Some(value) = arg.strip_prefix("--lint-level=") {
+            return LintLevel::parse(value).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_levels_and_lists() {
+        let warning = |code: &str| {
+            Diagnostic::new("a.py", 1, 1, Severity::Warning, "finding").with_code(code)
+        };
+        let mut policy = LintPolicy {
+            deny: vec!["F401".to_string()],
+            allow: vec!["E501".to_string()],
+            ..Default::default()
+        };
+
+        assert!(policy.apply(vec![warning("W291")]).0);
+        assert!(!policy.apply(vec![warning("F401")]).0);
+        assert!(policy.apply(vec![warning("E501")]).1.is_empty());
+
+        policy.level = LintLevel::Deny;
+        assert!(!policy.apply(vec![warning("W291")]).0);
+        assert!(policy.apply(vec![warning("E501")]).0);
+    }
+
+    #[test]
+    fn test_level_from_args() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            level_from_args(args(&["gate1", "--lint-level", "deny"])).unwrap(),
+            Some(LintLevel::Deny)
+        );
+        assert_eq!(
+            level_from_args(args(&["gate1", "--lint-level=warn"])).unwrap(),
+            Some(LintLevel::Warn)
+        );
+        assert_eq!(level_from_args(args(&["gate1"])).unwrap(), None);
+        assert!(level_from_args(args(&["gate1", "--lint-level", "loud"])).is_err());
+    }
+}
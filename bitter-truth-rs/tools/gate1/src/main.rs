@@ -1,12 +1,36 @@
-use bt_core::{error_exit, log_stderr, success_exit, Context, LogEntry};
+mod diagnostics;
+mod lint;
+mod project;
+mod scratch;
+mod toolchain;
+
+use bt_core::{log_stderr, BtError, Context, LogEntry, ToolError};
+use diagnostics::{Diagnostic, FailOn, Severity};
+use lint::LintLevel;
+use scratch::ScratchCrate;
 use serde::{Deserialize, Serialize};
-use std::io::Read;
-use std::process::Command;
-use std::time::SystemTime;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use toolchain::{CheckRun, Stage, Toolchain};
 
 #[derive(Debug, Deserialize)]
 struct Gate1Input {
+    /// A source file, or a project directory checked as a whole
+    #[serde(default)]
     code_path: String,
+    /// Further files or directories to check alongside `code_path`
+    #[serde(default)]
+    code_paths: Vec<String>,
+    /// Whether lint warnings fail the gate; `--lint-level` overrides this
+    #[serde(default)]
+    lint_level: Option<LintLevel>,
+    /// `error` (default), `warning` or `none`; overrides `[gate1] fail_on`
+    #[serde(default)]
+    fail_on: Option<FailOn>,
+    /// Run formatters in write mode before checking; also set by `--fix`
+    #[serde(default)]
+    fix: bool,
     language: String,
     #[serde(default)]
     context: Context,
@@ -19,40 +43,105 @@ struct Gate1Output {
     lint_ok: bool,
     type_ok: bool,
     errors: Vec<String>,
+    /// Warning-level findings; they fail the gate only with `fail_on: warning`
+    warnings: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+    files: Vec<FileResult>,
+    /// Files rewritten by `--fix`
+    fixed: Vec<String>,
+    /// A check was killed at the `context.timeout_seconds` deadline
+    timed_out: bool,
     was_dry_run: bool,
 }
 
-fn main() {
-    let start = SystemTime::now();
-    let mut input_str = String::new();
-    if std::io::stdin().read_to_string(&mut input_str).is_err() {
-        eprintln!("Failed to read stdin");
-        std::process::exit(1);
+/// Outcome for one checked file
+#[derive(Debug, Serialize)]
+struct FileResult {
+    path: String,
+    passed: bool,
+    timed_out: bool,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Diagnostic code marking a check killed at the deadline
+const TIMEOUT_CODE: &str = "timeout";
+
+impl Gate1Output {
+    /// Build a result; `errors` holds check-level failures that produced no
+    /// parseable diagnostics, followed by every error diagnostic
+    fn checked(syntax_ok: bool, lint_ok: bool, type_ok: bool, mut errors: Vec<String>, diagnostics: Vec<Diagnostic>) -> Self {
+        let timed_out = diagnostics.iter().any(|d| d.code.as_deref() == Some(TIMEOUT_CODE));
+        let at = |severity: Severity| diagnostics.iter().filter(move |d| d.severity == severity).map(|d| d.to_string());
+        errors.extend(at(Severity::Error));
+        let warnings = at(Severity::Warning).collect();
+        Self {
+            passed: syntax_ok && lint_ok && type_ok,
+            syntax_ok,
+            lint_ok,
+            type_ok,
+            errors,
+            warnings,
+            diagnostics,
+            files: vec![],
+            fixed: vec![],
+            timed_out,
+            was_dry_run: false,
+        }
     }
 
-    let input: Gate1Input = match serde_json::from_str(&input_str) {
-        Ok(i) => i,
-        Err(e) => {
-            let log = LogEntry::error(format!("Invalid JSON input: {}", e), "unknown".to_string());
-            log_stderr(&log);
-            error_exit(format!("Invalid JSON: {}", e), "unknown".to_string(), start);
+    /// Merge per-file results into one, all checks passing only if every file passed
+    fn combine(results: Vec<(String, Gate1Output)>) -> Self {
+        let mut combined = Self::checked(true, true, true, vec![], vec![]);
+        for (path, result) in results {
+            combined.syntax_ok &= result.syntax_ok;
+            combined.lint_ok &= result.lint_ok;
+            combined.type_ok &= result.type_ok;
+            combined.timed_out |= result.timed_out;
+            combined.errors.extend(result.errors.iter().cloned());
+            combined.warnings.extend(result.warnings.iter().cloned());
+            combined.diagnostics.extend(result.diagnostics);
+            combined.files.push(FileResult {
+                path,
+                passed: result.passed,
+                timed_out: result.timed_out,
+                errors: result.errors,
+                warnings: result.warnings,
+            });
         }
-    };
+        combined.passed = combined.syntax_ok && combined.lint_ok && combined.type_ok;
+        combined
+    }
+
+    /// Re-decide `passed` (overall and per file) for a severity threshold
+    fn apply_threshold(&mut self, fail_on: FailOn) {
+        let decide = |checks_ok: bool, warnings: &[String]| match fail_on {
+            FailOn::Error => checks_ok,
+            FailOn::Warning => checks_ok && warnings.is_empty(),
+            FailOn::None => true,
+        };
+        for file in &mut self.files {
+            file.passed = decide(file.passed, &file.warnings);
+        }
+        self.passed = decide(self.syntax_ok && self.lint_ok && self.type_ok, &self.warnings);
+    }
+}
+
+fn main() -> ExitCode {
+    bt_core::run(gate1)
+}
 
+fn gate1(input: Gate1Input) -> Result<Gate1Output, ToolError> {
     let trace_id = input.context.trace_id.clone();
     let dry_run = input.context.dry_run;
 
     // Validate required fields
-    if input.code_path.is_empty() {
-        let log = LogEntry::error("code_path is required", trace_id.clone());
-        log_stderr(&log);
-        error_exit("code_path is required".to_string(), trace_id, start);
+    if input.code_path.is_empty() && input.code_paths.is_empty() {
+        return Err(BtError::InvalidInput("code_path is required".to_string()).into());
     }
 
     if input.language.is_empty() {
-        let log = LogEntry::error("language is required", trace_id.clone());
-        log_stderr(&log);
-        error_exit("language is required".to_string(), trace_id, start);
+        return Err(BtError::InvalidInput("language is required".to_string()).into());
     }
 
     // Dry run mode
@@ -60,192 +149,602 @@ fn main() {
         let log = LogEntry::info("dry-run mode - skipping validation", trace_id.clone());
         log_stderr(&log);
 
-        let output = Gate1Output {
+        return Ok(Gate1Output {
             passed: true,
             syntax_ok: true,
             lint_ok: true,
             type_ok: true,
             errors: vec![],
+            warnings: vec![],
+            diagnostics: vec![],
+            files: vec![],
+            fixed: vec![],
+            timed_out: false,
             was_dry_run: true,
-        };
-
-        success_exit(output, trace_id.clone(), start);
+        });
     }
 
-    // Check file exists
-    if !std::path::Path::new(&input.code_path).exists() {
-        let log = LogEntry::error(
-            format!("code file not found: {}", input.code_path),
-            trace_id.clone(),
-        );
+    let Some(language) = canonical_language(&input.language) else {
+        let log = LogEntry::error(format!("unsupported language: {}", input.language), trace_id.clone());
         log_stderr(&log);
-        error_exit(
-            format!("Code file not found: {}", input.code_path),
-            trace_id,
-            start,
-        );
+        let message = format!("Unsupported language: {}", input.language);
+        return Err(gate_failed(Gate1Output::checked(false, false, false, vec![message], vec![])));
+    };
+
+    // Every check command shares one deadline; a hung checker is killed
+    // rather than wedging the task until the platform kills it
+    toolchain::set_deadline(input.context.timeout_seconds.map(|s| Instant::now() + Duration::from_secs(s)));
+
+    let targets = project::collect(&input.code_path, &input.code_paths, language)?;
+
+    // [gate1.<language>.<stage>] in the config file overrides the built-in commands
+    let mut toolchain: Toolchain = bt_core::config::load_section("gate1")?;
+    if let Some(level) = lint::level_from_args(std::env::args())?.or(input.lint_level) {
+        toolchain.lint.level = level;
     }
 
     let log = LogEntry::info("starting Gate 1 validation", trace_id.clone())
         .with_extra("code_path", serde_json::Value::String(input.code_path.clone()))
-        .with_extra("language", serde_json::Value::String(input.language.clone()));
+        .with_extra("files", serde_json::json!(targets.files.len()))
+        .with_extra("language", serde_json::Value::String(language.to_string()))
+        .with_extra("lint_level", serde_json::json!(toolchain.lint.level));
     log_stderr(&log);
 
-    let result = match input.language.as_str() {
-        "rust" | "rs" => check_rust(&input.code_path, &trace_id),
-        "python" | "py" => check_python(&input.code_path, &trace_id),
-        "typescript" | "ts" => check_typescript(&input.code_path, &trace_id),
-        "go" => check_go(&input.code_path, &trace_id),
-        lang => {
-            let log = LogEntry::error(format!("unsupported language: {}", lang), trace_id.clone());
-            log_stderr(&log);
-            Gate1Output {
-                passed: false,
-                syntax_ok: false,
-                lint_ok: false,
-                type_ok: false,
-                errors: vec![format!("Unsupported language: {}", lang)],
-                was_dry_run: false,
-            }
-        }
+    // Heal formatting before checking, so it does not cost a generation retry
+    let fixed = if input.fix || std::env::args().any(|a| a == "--fix") {
+        apply_fixes(&toolchain, language, &targets.files, &trace_id)
+    } else {
+        vec![]
     };
 
+    let project = targets.dir.as_deref().and_then(|dir| check_project(&toolchain, language, dir, &targets.files, &trace_id));
+    let mut result = match project {
+        Some(result) => result,
+        None => Gate1Output::combine(check_files(&toolchain, language, &targets.files, &trace_id)),
+    };
+    result.fixed = fixed;
+    let fail_on = input.fail_on.or(toolchain.fail_on).unwrap_or_default();
+    result.apply_threshold(fail_on);
+
     let passed = result.passed;
     let log = LogEntry::info("Gate 1 validation complete", trace_id.clone())
         .with_extra("passed", serde_json::Value::Bool(passed));
     log_stderr(&log);
 
     if passed {
-        success_exit(result, trace_id, start);
+        Ok(result)
     } else {
-        error_exit(
-            format!("Gate 1 validation failed: {}", result.errors.join("; ")),
-            trace_id,
-            start,
-        );
+        Err(gate_failed(result))
     }
 }
 
-fn check_rust(code_path: &str, trace_id: &str) -> Gate1Output {
-    let log = LogEntry::debug("checking Rust syntax and types", trace_id.to_string());
+fn gate_failed(result: Gate1Output) -> ToolError {
+    // With `fail_on: warning` the failure may consist of warnings alone
+    let reasons = if result.errors.is_empty() { &result.warnings } else { &result.errors };
+    let message = format!("Gate 1 validation failed: {}", reasons.join("; "));
+    let error = if result.timed_out { BtError::Timeout(message) } else { BtError::GateFailed(message) };
+    ToolError::from(error)
+        .with_detail("syntax_ok", serde_json::Value::Bool(result.syntax_ok))
+        .with_detail("lint_ok", serde_json::Value::Bool(result.lint_ok))
+        .with_detail("type_ok", serde_json::Value::Bool(result.type_ok))
+        .with_detail("errors", serde_json::json!(result.errors))
+        .with_detail("warnings", serde_json::json!(result.warnings))
+        .with_detail("diagnostics", serde_json::json!(result.diagnostics))
+        .with_detail("files", serde_json::json!(result.files))
+        .with_detail("fixed", serde_json::json!(result.fixed))
+        .with_detail("timed_out", serde_json::Value::Bool(result.timed_out))
+}
+
+/// Run the language's formatter over each file; returns the files it changed
+fn apply_fixes(toolchain: &Toolchain, language: &str, files: &[String], trace_id: &str) -> Vec<String> {
+    if toolchain.command(language, Stage::Fix).is_none() {
+        let log = LogEntry::warn("no formatter for language, --fix ignored", trace_id.to_string())
+            .with_extra("language", serde_json::Value::String(language.to_string()));
+        log_stderr(&log);
+        return vec![];
+    }
+
+    let fixed: Vec<String> = files
+        .iter()
+        .filter(|file| {
+            let before = std::fs::read(file).ok();
+            run_stage(toolchain, language, Stage::Fix, file, trace_id);
+            std::fs::read(file).ok() != before
+        })
+        .cloned()
+        .collect();
+
+    let log = LogEntry::info("formatting fixes applied", trace_id.to_string())
+        .with_extra("fixed", serde_json::json!(fixed));
     log_stderr(&log);
+    fixed
+}
 
-    // Check syntax with rustc
-    let syntax_check = Command::new("rustfmt")
-        .arg("--check")
-        .arg(code_path)
-        .output();
-
-    let syntax_ok = syntax_check.map(|o| o.status.success()).unwrap_or(true);
-
-    // Try cargo check if Cargo.toml exists
-    let has_cargo = std::path::Path::new("Cargo.toml").exists();
-    let type_ok = if has_cargo {
-        Command::new("cargo")
-            .arg("check")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-    } else {
-        // Fallback: just check with rustc
-        Command::new("rustc")
-            .arg("--crate-type")
-            .arg("bin")
-            .arg(code_path)
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-    };
+/// Map accepted language names and aliases to the names used in config
+fn canonical_language(language: &str) -> Option<&'static str> {
+    Some(match language {
+        "rust" | "rs" => "rust",
+        "python" | "py" => "python",
+        "typescript" | "ts" => "typescript",
+        "go" => "go",
+        "javascript" | "js" | "node" => "javascript",
+        "nushell" | "nu" => "nushell",
+        "bash" | "shell" => "bash",
+        "sh" => "sh",
+        _ => return None,
+    })
+}
+
+/// Check files concurrently, up to one per CPU at a time
+fn check_files(toolchain: &Toolchain, language: &str, files: &[String], trace_id: &str) -> Vec<(String, Gate1Output)> {
+    let width = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    files
+        .chunks(width)
+        .flat_map(|batch| {
+            std::thread::scope(|s| {
+                let handles: Vec<_> = batch
+                    .iter()
+                    .map(|file| s.spawn(move || (file.clone(), check_file(toolchain, language, file, trace_id))))
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e))).collect::<Vec<_>>()
+            })
+        })
+        .collect()
+}
+
+/// Run two checks on separate threads and wait for both
+fn join<A: Send, B: Send>(a: impl FnOnce() -> A + Send, b: impl FnOnce() -> B + Send) -> (A, B) {
+    std::thread::scope(|s| {
+        let b = s.spawn(b);
+        let a = a();
+        (a, b.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+    })
+}
+
+fn check_file(toolchain: &Toolchain, language: &str, code_path: &str, trace_id: &str) -> Gate1Output {
+    match language {
+        "rust" => check_rust(toolchain, code_path, trace_id),
+        "python" => check_python(toolchain, code_path, trace_id),
+        "typescript" => check_typescript(toolchain, code_path, trace_id),
+        "go" => check_go(toolchain, code_path, trace_id),
+        "javascript" => check_javascript(toolchain, code_path, trace_id),
+        "nushell" => check_nushell(toolchain, code_path, trace_id),
+        shell => check_shell(toolchain, code_path, shell, trace_id),
+    }
+}
 
-    Gate1Output {
-        passed: syntax_ok && type_ok,
-        syntax_ok,
-        lint_ok: true,
-        type_ok,
-        errors: if !syntax_ok {
-            vec!["Rust syntax check failed".to_string()]
-        } else if !type_ok {
-            vec!["Rust type check failed".to_string()]
-        } else {
-            vec![]
+/// Check a project directory in one run (`cargo check`, `tsc -p`) and
+/// attribute its diagnostics to files; `None` when `dir` is not a project
+/// for this language
+fn check_project(toolchain: &Toolchain, language: &str, dir: &Path, files: &[String], trace_id: &str) -> Option<Gate1Output> {
+    let is_project = project::marker(language).is_some_and(|m| dir.join(m).exists());
+    if !is_project && toolchain.configured(language, Stage::Project).is_none() {
+        return None;
+    }
+
+    let log = LogEntry::debug("checking project", trace_id.to_string())
+        .with_extra("dir", serde_json::Value::String(dir.display().to_string()));
+    log_stderr(&log);
+
+    let dir_path = dir.display().to_string();
+    let ((checked, lint), syntax_runs) = join(
+        || {
+            join(
+                || run_stage(toolchain, language, Stage::Project, &dir_path, trace_id),
+                || run_lint(toolchain, language, Stage::ProjectLint, &dir_path, trace_id),
+            )
+        },
+        // rustfmt per file for syntax; a missing rustfmt does not fail the gate
+        || match language {
+            "rust" => files.iter().filter_map(|f| run_stage(toolchain, language, Stage::Syntax, f, trace_id)).collect(),
+            _ => vec![],
         },
-        was_dry_run: false,
+    );
+    let (ok, mut diagnostics) = checked.map(|r| (r.success, r.diagnostics)).unwrap_or((false, vec![]));
+
+    // Lint counts only for code that builds; compiler errors would just repeat
+    let (lint_ok, lint) = if ok { lint } else { (true, vec![]) };
+    let lint: Vec<Diagnostic> = lint.into_iter().filter(|d| !diagnostics.contains(d)).collect();
+
+    let mut syntax_diagnostics = vec![];
+    let (syntax_ok, type_ok) = match language {
+        "typescript" => split_tsc(ok, &diagnostics),
+        "rust" => {
+            let mut syntax_ok = true;
+            for run in syntax_runs {
+                syntax_ok &= run.success;
+                syntax_diagnostics.extend(run.diagnostics);
+            }
+            (syntax_ok, ok)
+        }
+        _ => (true, ok),
+    };
+    diagnostics.extend(syntax_diagnostics);
+
+    let per_file = files
+        .iter()
+        .map(|file| {
+            let mine = |diags: &[Diagnostic]| -> Vec<Diagnostic> {
+                diags.iter().filter(|d| project::same_file(&d.file, file, dir)).cloned().collect()
+            };
+            let (file_lint_ok, mut found) = toolchain.lint.apply(mine(&lint));
+            let file_type_ok = !mine(&diagnostics).iter().any(|d| d.severity == Severity::Error);
+            found.extend(mine(&diagnostics));
+            (file.clone(), Gate1Output::checked(true, file_lint_ok, file_type_ok, vec![], found))
+        })
+        .collect();
+
+    let mut result = Gate1Output::combine(per_file);
+    result.errors.extend(fallback_error(ok, &diagnostics, "Project check failed"));
+    result.errors.extend(fallback_error(syntax_ok, &[], "Syntax check failed"));
+    // Diagnostics outside the listed files (e.g. build scripts) still count
+    for d in diagnostics.into_iter().chain(lint).filter(|d| !files.iter().any(|f| project::same_file(&d.file, f, dir))) {
+        match d.severity {
+            Severity::Error => result.errors.push(d.to_string()),
+            Severity::Warning => result.warnings.push(d.to_string()),
+            Severity::Info => {}
+        }
+        result.diagnostics.push(d);
+    }
+    result.timed_out = result.diagnostics.iter().any(|d| d.code.as_deref() == Some(TIMEOUT_CODE));
+    result.syntax_ok = syntax_ok;
+    result.lint_ok = lint_ok;
+    result.type_ok = type_ok;
+    result.passed = syntax_ok && lint_ok && type_ok;
+    Some(result)
+}
+
+/// TS1xxx codes are syntax errors, everything else is semantic
+fn split_tsc(ok: bool, diagnostics: &[Diagnostic]) -> (bool, bool) {
+    let is_syntax = |d: &Diagnostic| d.code.as_deref().is_some_and(|c| c.starts_with("TS1"));
+    let errors_where = |f: &dyn Fn(&Diagnostic) -> bool| {
+        diagnostics.iter().any(|d| d.severity == Severity::Error && f(d))
+    };
+    let syntax_ok = (ok || !diagnostics.is_empty()) && !errors_where(&is_syntax);
+    let type_ok = !errors_where(&|d| !is_syntax(d));
+    (syntax_ok, type_ok)
+}
+
+/// Record a failed check that produced no diagnostics of its own
+fn fallback_error(ok: bool, diagnostics: &[Diagnostic], message: &str) -> Option<String> {
+    let explained = diagnostics.iter().any(|d| d.severity == Severity::Error);
+    (!ok && !explained).then(|| message.to_string())
+}
+
+/// Run one stage's command; `None` when the language has no such stage or
+/// the program could not be started
+fn run_stage(toolchain: &Toolchain, language: &str, stage: Stage, code_path: &str, trace_id: &str) -> Option<CheckRun> {
+    run_stage_with(toolchain, language, stage, code_path, &[], trace_id)
+}
+
+/// `run_stage` with extra placeholder substitutions such as `{scratch}`
+fn run_stage_with(
+    toolchain: &Toolchain,
+    language: &str,
+    stage: Stage,
+    code_path: &str,
+    vars: &[(&str, &str)],
+    trace_id: &str,
+) -> Option<CheckRun> {
+    let command = toolchain.command(language, stage)?;
+    let mut run = command.run(code_path, vars);
+    match &mut run {
+        None => {
+            let log = LogEntry::debug("check command not available", trace_id.to_string())
+                .with_extra("program", serde_json::Value::String(command.program().to_string()));
+            log_stderr(&log);
+        }
+        Some(run) if run.timed_out => {
+            let message = format!("{} check ({}) timed out", stage.name(), command.program());
+            let log = LogEntry::warn(message.clone(), trace_id.to_string())
+                .with_extra("code_path", serde_json::Value::String(code_path.to_string()));
+            log_stderr(&log);
+            run.diagnostics.push(Diagnostic::new(code_path, 0, 0, Severity::Error, message).with_code(TIMEOUT_CODE));
+        }
+        Some(_) => {}
     }
+    run
+}
+
+/// Run a lint stage and apply the `[gate1.lint]` policy; a missing linter passes
+fn run_lint(toolchain: &Toolchain, language: &str, stage: Stage, code_path: &str, trace_id: &str) -> (bool, Vec<Diagnostic>) {
+    run_lint_with(toolchain, language, stage, code_path, &[], trace_id)
+}
+
+fn run_lint_with(
+    toolchain: &Toolchain,
+    language: &str,
+    stage: Stage,
+    code_path: &str,
+    vars: &[(&str, &str)],
+    trace_id: &str,
+) -> (bool, Vec<Diagnostic>) {
+    if toolchain.command(language, stage).is_none() {
+        return (true, vec![]);
+    }
+    // eslint only runs when the project opted in with a config file;
+    // a configured lint command always runs
+    if matches!(language, "javascript" | "typescript") && toolchain.configured(language, stage).is_none() {
+        let Some(config) = find_upwards(code_path, ESLINT_CONFIGS) else {
+            return (true, vec![]);
+        };
+        let log = LogEntry::debug("running eslint", trace_id.to_string())
+            .with_extra("config", serde_json::Value::String(config.display().to_string()));
+        log_stderr(&log);
+    }
+    let findings = run_stage_with(toolchain, language, stage, code_path, vars, trace_id)
+        .map(|r| r.diagnostics)
+        .unwrap_or_default();
+    toolchain.lint.apply(findings)
+}
+
+fn check_rust(toolchain: &Toolchain, code_path: &str, trace_id: &str) -> Gate1Output {
+    let log = LogEntry::debug("checking Rust syntax and types", trace_id.to_string());
+    log_stderr(&log);
+
+    // Compile inside a throwaway crate so neither cwd's Cargo.toml nor
+    // build output in cwd affects the result
+    let scratch = match ScratchCrate::new(code_path) {
+        Ok(scratch) => scratch,
+        Err(e) => {
+            let message = format!("Could not create scratch crate: {}", e);
+            return Gate1Output::checked(true, true, false, vec![message], vec![]);
+        }
+    };
+    let scratch_dir = scratch.dir().display().to_string();
+    let vars = [("{scratch}", scratch_dir.as_str())];
+
+    let (syntax, (typed, lint)) = join(
+        || run_stage(toolchain, "rust", Stage::Syntax, code_path, trace_id),
+        || {
+            join(
+                || run_stage_with(toolchain, "rust", Stage::Type, code_path, &vars, trace_id),
+                || run_lint_with(toolchain, "rust", Stage::Lint, code_path, &vars, trace_id),
+            )
+        },
+    );
+
+    // A missing rustfmt does not fail the gate
+    let (syntax_ok, mut diagnostics) = syntax.map(|r| (r.success, r.diagnostics)).unwrap_or((true, vec![]));
+    let (type_ok, type_diagnostics) = typed.map(|r| (r.success, r.diagnostics)).unwrap_or((false, vec![]));
+    diagnostics.extend(type_diagnostics);
+
+    let errors = [
+        fallback_error(syntax_ok, &[], "Rust syntax check failed"),
+        fallback_error(type_ok, &diagnostics, "Rust type check failed"),
+    ];
+
+    // clippy repeats compiler errors and warnings, so lint counts only for code that builds
+    let (lint_ok, lint) = if type_ok { lint } else { (true, vec![]) };
+    let lint: Vec<Diagnostic> = lint.into_iter().filter(|d| !diagnostics.contains(d)).collect();
+    diagnostics.extend(lint);
+
+    Gate1Output::checked(syntax_ok, lint_ok, type_ok, errors.into_iter().flatten().collect(), diagnostics)
 }
 
-fn check_python(code_path: &str, trace_id: &str) -> Gate1Output {
+fn check_python(toolchain: &Toolchain, code_path: &str, trace_id: &str) -> Gate1Output {
     let log = LogEntry::debug("checking Python syntax", trace_id.to_string());
     log_stderr(&log);
 
-    let check = Command::new("python3")
-        .arg("-m")
-        .arg("py_compile")
-        .arg(code_path)
-        .output();
-
-    let passed = check.map(|o| o.status.success()).unwrap_or(false);
-
-    Gate1Output {
-        passed,
-        syntax_ok: passed,
-        lint_ok: true,
-        type_ok: true,
-        errors: if !passed {
-            vec!["Python syntax check failed".to_string()]
-        } else {
-            vec![]
+    let (syntax, (typed, lint)) = join(
+        || run_stage(toolchain, "python", Stage::Syntax, code_path, trace_id),
+        || {
+            join(
+                || {
+                    // mypy fails most untyped code, so it only runs for projects that set it up
+                    let opted_in = toolchain.configured("python", Stage::Type).is_some() || mypy_config(code_path).is_some();
+                    opted_in.then(|| run_stage(toolchain, "python", Stage::Type, code_path, trace_id)).flatten()
+                },
+                || run_lint(toolchain, "python", Stage::Lint, code_path, trace_id),
+            )
         },
-        was_dry_run: false,
+    );
+
+    let (syntax_ok, mut diagnostics) = syntax.map(|r| (r.success, r.diagnostics)).unwrap_or((false, vec![]));
+    let errors = fallback_error(syntax_ok, &diagnostics, "Python syntax check failed");
+
+    // mypy and ruff are optional and only count for code that parses;
+    // ruff would repeat the syntax error
+    let mut type_ok = true;
+    let mut lint_ok = true;
+    if syntax_ok {
+        if let Some(run) = typed {
+            type_ok = !run.diagnostics.iter().any(|d| d.severity == Severity::Error);
+            diagnostics.extend(run.diagnostics);
+        }
+        let (ok, lint) = lint;
+        lint_ok = ok;
+        diagnostics.extend(lint);
     }
+
+    Gate1Output::checked(syntax_ok, lint_ok, type_ok, errors.into_iter().collect(), diagnostics)
 }
 
-fn check_typescript(code_path: &str, trace_id: &str) -> Gate1Output {
+fn check_typescript(toolchain: &Toolchain, code_path: &str, trace_id: &str) -> Gate1Output {
     let log = LogEntry::debug("checking TypeScript syntax", trace_id.to_string());
     log_stderr(&log);
 
-    // Try tsc if available
-    let check = Command::new("tsc")
-        .arg("--noEmit")
-        .arg(code_path)
-        .output();
-
-    let passed = check.map(|o| o.status.success()).unwrap_or(false);
-
-    Gate1Output {
-        passed,
-        syntax_ok: passed,
-        lint_ok: true,
-        type_ok: true,
-        errors: if !passed {
-            vec!["TypeScript syntax check failed".to_string()]
-        } else {
-            vec![]
-        },
-        was_dry_run: false,
-    }
+    let (checked, (lint_ok, lint)) = join(
+        || run_stage(toolchain, "typescript", Stage::Syntax, code_path, trace_id),
+        || run_lint(toolchain, "typescript", Stage::Lint, code_path, trace_id),
+    );
+    let (ok, mut diagnostics) = checked.map(|r| (r.success, r.diagnostics)).unwrap_or((false, vec![]));
+
+    let (syntax_ok, type_ok) = split_tsc(ok, &diagnostics);
+    let errors = fallback_error(ok, &diagnostics, "TypeScript syntax check failed");
+    diagnostics.extend(lint);
+
+    Gate1Output::checked(syntax_ok, lint_ok, type_ok, errors.into_iter().collect(), diagnostics)
 }
 
-fn check_go(code_path: &str, trace_id: &str) -> Gate1Output {
+fn check_go(toolchain: &Toolchain, code_path: &str, trace_id: &str) -> Gate1Output {
     let log = LogEntry::debug("checking Go syntax", trace_id.to_string());
     log_stderr(&log);
 
-    let check = Command::new("go")
-        .arg("fmt")
-        .arg(code_path)
-        .output();
-
-    let passed = check.map(|o| o.status.success()).unwrap_or(false);
-
-    Gate1Output {
-        passed,
-        syntax_ok: passed,
-        lint_ok: true,
-        type_ok: true,
-        errors: if !passed {
-            vec!["Go syntax check failed".to_string()]
-        } else {
-            vec![]
-        },
-        was_dry_run: false,
+    let (syntax, lint) = join(
+        || run_stage(toolchain, "go", Stage::Syntax, code_path, trace_id),
+        || run_lint(toolchain, "go", Stage::Lint, code_path, trace_id),
+    );
+    let (passed, mut diagnostics) = syntax.map(|r| (r.success, r.diagnostics)).unwrap_or((false, vec![]));
+    let errors = fallback_error(passed, &diagnostics, "Go syntax check failed");
+
+    // go vet findings only count for code that parses
+    let (lint_ok, lint) = if passed { lint } else { (true, vec![]) };
+    diagnostics.extend(lint);
+
+    Gate1Output::checked(passed, lint_ok, true, errors.into_iter().collect(), diagnostics)
+}
+
+fn check_nushell(toolchain: &Toolchain, code_path: &str, trace_id: &str) -> Gate1Output {
+    let log = LogEntry::debug("checking Nushell syntax", trace_id.to_string());
+    log_stderr(&log);
+
+    let (passed, diagnostics) = run_stage(toolchain, "nushell", Stage::Syntax, code_path, trace_id)
+        .map(|r| (r.success, r.diagnostics))
+        .unwrap_or((false, vec![]));
+
+    let errors = fallback_error(passed, &diagnostics, "Nushell syntax check failed");
+    Gate1Output::checked(passed, true, true, errors.into_iter().collect(), diagnostics)
+}
+
+fn check_shell(toolchain: &Toolchain, code_path: &str, shell: &str, trace_id: &str) -> Gate1Output {
+    let log = LogEntry::debug("checking shell syntax", trace_id.to_string())
+        .with_extra("shell", serde_json::Value::String(shell.to_string()));
+    log_stderr(&log);
+
+    // shellcheck is optional; without it only syntax is enforced
+    let (syntax, (lint_ok, lint)) = join(
+        || run_stage(toolchain, shell, Stage::Syntax, code_path, trace_id),
+        || run_lint(toolchain, shell, Stage::Lint, code_path, trace_id),
+    );
+    let (syntax_ok, mut diagnostics) = syntax.map(|r| (r.success, r.diagnostics)).unwrap_or((false, vec![]));
+    let syntax_failed = fallback_error(syntax_ok, &diagnostics, "Shell syntax check failed");
+    diagnostics.extend(lint);
+
+    Gate1Output::checked(syntax_ok, lint_ok, true, syntax_failed.into_iter().collect(), diagnostics)
+}
+
+const ESLINT_CONFIGS: &[&str] = &[
+    "eslint.config.js",
+    "eslint.config.mjs",
+    "eslint.config.cjs",
+    ".eslintrc.js",
+    ".eslintrc.cjs",
+    ".eslintrc.json",
+    ".eslintrc.yml",
+    ".eslintrc.yaml",
+    ".eslintrc",
+];
+
+fn check_javascript(toolchain: &Toolchain, code_path: &str, trace_id: &str) -> Gate1Output {
+    let log = LogEntry::debug("checking JavaScript syntax", trace_id.to_string());
+    log_stderr(&log);
+
+    let (syntax, (lint_ok, lint)) = join(
+        || run_stage(toolchain, "javascript", Stage::Syntax, code_path, trace_id),
+        || run_lint(toolchain, "javascript", Stage::Lint, code_path, trace_id),
+    );
+    let (syntax_ok, mut diagnostics) = syntax.map(|r| (r.success, r.diagnostics)).unwrap_or((false, vec![]));
+    let syntax_failed = fallback_error(syntax_ok, &diagnostics, "JavaScript syntax check failed");
+    diagnostics.extend(lint);
+
+    Gate1Output::checked(syntax_ok, lint_ok, true, syntax_failed.into_iter().collect(), diagnostics)
+}
+
+/// A mypy config covering `code_path`: mypy.ini, .mypy.ini, or a mypy
+/// section in pyproject.toml or setup.cfg, in the file's directory or above
+fn mypy_config(code_path: &str) -> Option<std::path::PathBuf> {
+    let start = std::fs::canonicalize(code_path).ok()?;
+    let has_section = |path: &Path, section: &str| {
+        std::fs::read_to_string(path).is_ok_and(|text| text.lines().any(|l| l.trim().starts_with(section)))
+    };
+    start.ancestors().skip(usize::from(!start.is_dir())).find_map(|dir| {
+        let candidates = [
+            (dir.join("mypy.ini"), None),
+            (dir.join(".mypy.ini"), None),
+            (dir.join("pyproject.toml"), Some("[tool.mypy")),
+            (dir.join("setup.cfg"), Some("[mypy")),
+        ];
+        candidates
+            .into_iter()
+            .find(|(path, section)| match section {
+                None => path.exists(),
+                Some(section) => has_section(path, section),
+            })
+            .map(|(path, _)| path)
+    })
+}
+
+/// Search the file's directory and its ancestors for any of `names`
+fn find_upwards(code_path: &str, names: &[&str]) -> Option<std::path::PathBuf> {
+    let start = std::fs::canonicalize(code_path).ok()?;
+    start
+        .ancestors()
+        .skip(1)
+        .flat_map(|dir| names.iter().map(move |n| dir.join(n)))
+        .find(|p| p.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fix_reports_only_changed_files() {
+        let dir = std::env::temp_dir().join(format!("bt-gate1-fix-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let messy = dir.join("messy.py").display().to_string();
+        let tidy = dir.join("tidy.py").display().to_string();
+        std::fs::write(&messy, "x = 1   \n").unwrap();
+        std::fs::write(&tidy, "x = 1\n").unwrap();
+
+        // Strips trailing spaces, standing in for a real formatter
+        let toml = "[gate1.python.fix]\ncommand = [\"sed\", \"-i\", \"s/ *$//\", \"{file}\"]\nformat = \"none\"\n";
+        let toolchain: Toolchain = bt_core::config::section_from_toml_str(toml, "gate1").unwrap();
+        let files = vec![messy.clone(), tidy];
+        assert_eq!(apply_fixes(&toolchain, "python", &files, "t"), vec![messy.clone()]);
+        assert_eq!(std::fs::read_to_string(&messy).unwrap(), "x = 1\n");
+        assert!(apply_fixes(&toolchain, "python", &files, "t").is_empty());
+
+        assert!(apply_fixes(&toolchain, "nushell", &files, "t").is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fail_on_threshold() {
+        let warned = || {
+            let warning = Diagnostic::new("a.py", 3, 1, Severity::Warning, "unused import");
+            Gate1Output::combine(vec![("a.py".to_string(), Gate1Output::checked(true, true, true, vec![], vec![warning]))])
+        };
+        let decided = |mut result: Gate1Output, fail_on| {
+            result.apply_threshold(fail_on);
+            (result.passed, result.files[0].passed)
+        };
+        assert_eq!(decided(warned(), FailOn::Error), (true, true));
+        assert_eq!(decided(warned(), FailOn::Warning), (false, false));
+        assert_eq!(decided(warned(), FailOn::None), (true, true));
+
+        let broken = || {
+            let error = Diagnostic::new("a.py", 1, 1, Severity::Error, "invalid syntax");
+            Gate1Output::combine(vec![("a.py".to_string(), Gate1Output::checked(false, true, true, vec![], vec![error]))])
+        };
+        assert_eq!(decided(broken(), FailOn::Error), (false, false));
+        assert_eq!(decided(broken(), FailOn::None), (true, true));
+    }
+
+    #[test]
+    fn test_mypy_runs_only_with_a_config() {
+        let root = std::env::temp_dir().join(format!("bt-gate1-mypy-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("pkg")).unwrap();
+        let file = root.join("pkg/app.py");
+        std::fs::write(&file, "def f(x):\n    return x\n").unwrap();
+        std::fs::write(root.join("pyproject.toml"), "[tool.ruff]\nline-length = 100\n").unwrap();
+        assert_eq!(mypy_config(file.to_str().unwrap()), None);
+
+        std::fs::write(root.join("pyproject.toml"), "[tool.mypy]\nstrict = true\n").unwrap();
+        assert_eq!(mypy_config(file.to_str().unwrap()), Some(root.canonicalize().unwrap().join("pyproject.toml")));
+        std::fs::write(root.join("pkg/mypy.ini"), "[mypy]\n").unwrap();
+        assert_eq!(mypy_config(root.join("pkg").to_str().unwrap()), Some(root.canonicalize().unwrap().join("pkg/mypy.ini")));
+        std::fs::remove_dir_all(&root).unwrap();
     }
 }
@@ -0,0 +1,137 @@
+// Expanding gate1 input (a file, a directory, or a list) into files to check
+
+use bt_core::BtError;
+use std::path::{Path, PathBuf};
+
+/// Directories never worth checking inside a generated project
+const SKIP_DIRS: &[&str] = &["target", "node_modules", "__pycache__", "vendor", "dist"];
+
+/// What gate1 was asked to check
+#[derive(Debug)]
+pub struct Targets {
+    /// Set when the input was a single directory
+    pub dir: Option<PathBuf>,
+    pub files: Vec<String>,
+}
+
+/// Source extensions for a canonical language name
+pub fn extensions(language: &str) -> &'static [&'static str] {
+    match language {
+        "rust" => &["rs"],
+        "python" => &["py"],
+        "typescript" => &["ts", "tsx"],
+        "go" => &["go"],
+        "javascript" => &["js", "mjs", "cjs"],
+        "nushell" => &["nu"],
+        "bash" => &["sh", "bash"],
+        "sh" => &["sh"],
+        _ => &[],
+    }
+}
+
+/// File whose presence makes a directory a project checked as a whole
+pub fn marker(language: &str) -> Option<&'static str> {
+    match language {
+        "rust" => Some("Cargo.toml"),
+        "typescript" => Some("tsconfig.json"),
+        _ => None,
+    }
+}
+
+/// Expand `code_path` and `code_paths` into the files to check
+pub fn collect(code_path: &str, code_paths: &[String], language: &str) -> Result<Targets, BtError> {
+    let inputs: Vec<&str> = std::iter::once(code_path)
+        .chain(code_paths.iter().map(String::as_str))
+        .filter(|p| !p.is_empty())
+        .collect();
+    if inputs.is_empty() {
+        return Err(BtError::InvalidInput(
+            "code_path or code_paths is required".to_string(),
+        ));
+    }
+
+    let mut files = vec![];
+    let mut dirs = vec![];
+    for input in &inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            walk(path, extensions(language), &mut files);
+            dirs.push(path.to_path_buf());
+        } else if path.exists() {
+            files.push(input.to_string());
+        } else {
+            return Err(BtError::NotFound(format!("Code file not found: {}", input)));
+        }
+    }
+    let mut seen = std::collections::HashSet::new();
+    files.retain(|f| seen.insert(f.clone()));
+
+    if files.is_empty() {
+        return Err(BtError::NotFound(format!(
+            "No {} files found in: {}",
+            language,
+            inputs.join(", ")
+        )));
+    }
+
+    let dir = match (inputs.len(), dirs.pop()) {
+        (1, Some(dir)) => Some(dir),
+        _ => None,
+    };
+    Ok(Targets { dir, files })
+}
+
+fn walk(dir: &Path, extensions: &[&str], files: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIP_DIRS.contains(&name) {
+                walk(&path, extensions, files);
+            }
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| extensions.contains(&e))
+        {
+            files.push(path.display().to_string());
+        }
+    }
+}
+
+/// Whether a diagnostic's file (often relative to the project) is `path`
+pub fn same_file(diagnostic_file: &str, path: &str, dir: &Path) -> bool {
+    let resolved = dir.join(diagnostic_file);
+    match (std::fs::canonicalize(resolved), std::fs::canonicalize(path)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => Path::new(path).ends_with(diagnostic_file),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_walks_directory() {
+        let dir = std::env::temp_dir().join(format!("gate1-project-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src/bin")).unwrap();
+        std::fs::create_dir_all(dir.join("target/debug")).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "").unwrap();
+        std::fs::write(dir.join("src/main.rs"), "").unwrap();
+        std::fs::write(dir.join("src/bin/tool.rs"), "").unwrap();
+        std::fs::write(dir.join("target/debug/build.rs"), "").unwrap();
+
+        let targets = collect(dir.to_str().unwrap(), &[], "rust").unwrap();
+        assert_eq!(targets.dir.as_deref(), Some(dir.as_path()));
+        assert_eq!(targets.files.len(), 2);
+        assert!(targets.files[0].ends_with("src/bin/tool.rs"));
+        assert!(same_file("src/main.rs", &targets.files[1], &dir));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
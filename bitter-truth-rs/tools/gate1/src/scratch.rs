@@ -0,0 +1,87 @@
+// Throwaway cargo crate wrapping a single Rust file, so checks never touch cwd
+
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static NEXT: AtomicU32 = AtomicU32::new(0);
+
+/// A temp crate whose only target is the submitted file; removed on drop
+pub struct ScratchCrate {
+    dir: PathBuf,
+}
+
+impl ScratchCrate {
+    pub fn new(code_path: &str) -> std::io::Result<Self> {
+        let file = std::fs::canonicalize(code_path)?;
+        let source = std::fs::read_to_string(&file)?;
+
+        let dir = std::env::temp_dir().join(format!(
+            "bt-gate1-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir)?;
+        let scratch = Self { dir };
+
+        // Files without a main are checked as a library rather than failing on E0601
+        let target = if has_main(&source) {
+            "[[bin]]\nname = \"gate1_check\""
+        } else {
+            "[lib]"
+        };
+        let manifest = format!(
+            "[package]\nname = \"gate1_check\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n{}\npath = {:?}\n\n[workspace]\n",
+            target,
+            file.display().to_string()
+        );
+        std::fs::write(scratch.dir.join("Cargo.toml"), manifest)?;
+        Ok(scratch)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+/// A `fn main(` definition, not `fn main_loop` or `fn maintain`
+fn has_main(source: &str) -> bool {
+    Regex::new(r"\bfn\s+main\s*\(").unwrap().is_match(source)
+}
+
+impl Drop for ScratchCrate {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_crate_wraps_file_and_cleans_up() {
+        let file = std::env::temp_dir().join(format!("gate1-scratch-{}.rs", std::process::id()));
+        std::fs::write(&file, "pub fn add(a: i32, b: i32) -> i32 { a + b }\n").unwrap();
+
+        let scratch = ScratchCrate::new(file.to_str().unwrap()).unwrap();
+        let dir = scratch.dir().to_path_buf();
+        let manifest = std::fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("[lib]"));
+        assert!(manifest.contains(&format!(
+            "{:?}",
+            std::fs::canonicalize(&file).unwrap().display().to_string()
+        )));
+
+        drop(scratch);
+        assert!(!dir.exists());
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_has_main() {
+        assert!(has_main("fn main() {}"));
+        assert!(has_main("pub fn  main (){}"));
+        assert!(!has_main("fn main_loop() {}\nfn maintain() {}"));
+    }
+}
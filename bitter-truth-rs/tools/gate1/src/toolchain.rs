@@ -0,0 +1,410 @@
+// Per-language check commands, overridable from the [gate1] config table
+
+use crate::diagnostics::{self, Diagnostic, FailOn};
+use crate::lint::LintPolicy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Overall deadline shared by every check command in this run
+static DEADLINE: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Commands started after `deadline` time out immediately; running ones are killed at it
+pub fn set_deadline(deadline: Option<Instant>) {
+    if let Ok(mut d) = DEADLINE.lock() {
+        *d = deadline;
+    }
+}
+
+fn remaining() -> Option<Duration> {
+    let deadline = (*DEADLINE.lock().ok()?)?;
+    Some(deadline.saturating_duration_since(Instant::now()))
+}
+
+/// How to turn a command's stdout/stderr into diagnostics
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    RustcJson,
+    Tsc,
+    Mypy,
+    RuffJson,
+    PyCompile,
+    Colon,
+    GoVet,
+    NuCheck,
+    Shellcheck,
+    Eslint,
+    None,
+}
+
+impl OutputFormat {
+    fn parse(self, output: &str) -> Vec<Diagnostic> {
+        match self {
+            OutputFormat::RustcJson => diagnostics::parse_rustc_json(output),
+            OutputFormat::Tsc => diagnostics::parse_tsc(output),
+            OutputFormat::Mypy => diagnostics::parse_mypy(output),
+            OutputFormat::RuffJson => diagnostics::parse_ruff_json(output),
+            OutputFormat::PyCompile => diagnostics::parse_py_compile(output),
+            OutputFormat::Colon => diagnostics::parse_colon_format(output),
+            OutputFormat::GoVet => diagnostics::parse_go_vet(output),
+            OutputFormat::NuCheck => diagnostics::parse_nu_check(output),
+            OutputFormat::Shellcheck => diagnostics::parse_shellcheck(output),
+            OutputFormat::Eslint => diagnostics::parse_eslint(output),
+            OutputFormat::None => vec![],
+        }
+    }
+}
+
+/// A program plus arguments; `{file}` in any argument is replaced by the code
+/// path, `{file_nu}` by that path as a Nushell raw string literal, `{dir}` by
+/// the path's directory (or the path itself for a project) and, for single
+/// Rust files, `{scratch}` by the isolated wrapper crate
+#[derive(Debug, Clone, Deserialize)]
+pub struct CheckCommand {
+    pub command: Vec<String>,
+    #[serde(default = "default_format")]
+    pub format: OutputFormat,
+}
+
+fn default_format() -> OutputFormat {
+    OutputFormat::Colon
+}
+
+/// Outcome of running one check command
+pub struct CheckRun {
+    pub success: bool,
+    /// Killed at the deadline; output is discarded
+    pub timed_out: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl CheckCommand {
+    fn new(command: &[&str], format: OutputFormat) -> Self {
+        Self {
+            command: command.iter().map(|s| s.to_string()).collect(),
+            format,
+        }
+    }
+
+    /// The command line with every placeholder filled in
+    fn render(&self, file: &str, vars: &[(&str, &str)]) -> Vec<String> {
+        let path = Path::new(file);
+        let dir = if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(Path::new("."))
+        };
+        let dir = match dir.to_str() {
+            Some("") | None => ".",
+            Some(d) => d,
+        };
+        let file_nu = nu_raw_string(file);
+        self.command
+            .iter()
+            .map(|arg| {
+                vars.iter().fold(
+                    arg.replace("{file_nu}", &file_nu)
+                        .replace("{file}", file)
+                        .replace("{dir}", dir),
+                    |a, (k, v)| a.replace(k, v),
+                )
+            })
+            .collect()
+    }
+
+    pub fn program(&self) -> &str {
+        self.command.first().map(String::as_str).unwrap_or("")
+    }
+
+    /// Run against `file` with extra `(placeholder, value)` substitutions;
+    /// `None` when the program could not be started
+    pub fn run(&self, file: &str, vars: &[(&str, &str)]) -> Option<CheckRun> {
+        let rendered = self.render(file, vars);
+        let (program, args) = rendered.split_first()?;
+        let mut command = Command::new(program);
+        command.args(args);
+        let Some(output) = bt_core::process::output_with_timeout(&mut command, remaining()).ok()?
+        else {
+            return Some(CheckRun::timed_out());
+        };
+
+        let text = format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Some(CheckRun {
+            success: output.status.success(),
+            timed_out: false,
+            diagnostics: self.format.parse(&text),
+        })
+    }
+}
+
+/// `text` as a Nushell raw string, `r#'...'#`, with one more `#` than the
+/// longest run of `#` after a `'` in it so the text cannot close it early
+fn nu_raw_string(text: &str) -> String {
+    let longest = text
+        .split('\'')
+        .skip(1)
+        .map(|rest| rest.chars().take_while(|&c| c == '#').count())
+        .max()
+        .unwrap_or(0);
+    let hashes = "#".repeat(longest + 1);
+    format!("r{}'{}'{}", hashes, text, hashes)
+}
+
+impl CheckRun {
+    fn timed_out() -> Self {
+        Self {
+            success: false,
+            timed_out: true,
+            diagnostics: vec![],
+        }
+    }
+}
+
+/// Check stages a language may configure
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LanguageChecks {
+    pub syntax: Option<CheckCommand>,
+    pub lint: Option<CheckCommand>,
+    #[serde(rename = "type")]
+    pub type_check: Option<CheckCommand>,
+    /// Whole-project check, run once when a project directory is given
+    pub project: Option<CheckCommand>,
+    /// Whole-project lint, run alongside `project`
+    pub project_lint: Option<CheckCommand>,
+    /// Formatter run in write mode by `--fix`
+    pub fix: Option<CheckCommand>,
+}
+
+/// The `[gate1]` table: `[gate1.lint]` policy plus
+/// `[gate1.<language>.<stage>]` overrides on top of the built-in commands
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Toolchain {
+    #[serde(default)]
+    pub lint: LintPolicy,
+    /// Default for the input's `fail_on`
+    #[serde(default)]
+    pub fail_on: Option<FailOn>,
+    #[serde(flatten)]
+    pub languages: HashMap<String, LanguageChecks>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Syntax,
+    Lint,
+    Type,
+    Project,
+    ProjectLint,
+    Fix,
+}
+
+impl Stage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Syntax => "syntax",
+            Stage::Lint => "lint",
+            Stage::Type => "type",
+            Stage::Project => "project",
+            Stage::ProjectLint => "project lint",
+            Stage::Fix => "fix",
+        }
+    }
+}
+
+impl Toolchain {
+    /// Configured command for a stage, else the built-in default
+    pub fn command(&self, language: &str, stage: Stage) -> Option<CheckCommand> {
+        self.configured(language, stage)
+            .or_else(|| default_command(language, stage))
+    }
+
+    /// Only the user-configured command, if any
+    pub fn configured(&self, language: &str, stage: Stage) -> Option<CheckCommand> {
+        let checks = self.languages.get(language)?;
+        match stage {
+            Stage::Syntax => checks.syntax.clone(),
+            Stage::Lint => checks.lint.clone(),
+            Stage::Type => checks.type_check.clone(),
+            Stage::Project => checks.project.clone(),
+            Stage::ProjectLint => checks.project_lint.clone(),
+            Stage::Fix => checks.fix.clone(),
+        }
+    }
+}
+
+/// The commands gate1 has always used
+fn default_command(language: &str, stage: Stage) -> Option<CheckCommand> {
+    use OutputFormat::*;
+    let cmd = match (language, stage) {
+        ("rust", Stage::Syntax) => CheckCommand::new(&["rustfmt", "--check", "{file}"], None),
+        ("rust", Stage::Type) => CheckCommand::new(
+            &[
+                "cargo",
+                "check",
+                "--message-format=json",
+                "--manifest-path",
+                "{scratch}/Cargo.toml",
+                "--target-dir",
+                "{scratch}/target",
+            ],
+            RustcJson,
+        ),
+        ("rust", Stage::Fix) => CheckCommand::new(&["rustfmt", "{file}"], None),
+        ("rust", Stage::Lint) => CheckCommand::new(
+            // Own target dir, so clippy does not wait on the type check's build lock
+            &[
+                "cargo",
+                "clippy",
+                "--message-format=json",
+                "--manifest-path",
+                "{scratch}/Cargo.toml",
+                "--target-dir",
+                "{scratch}/lint-target",
+            ],
+            RustcJson,
+        ),
+        ("rust", Stage::ProjectLint) => CheckCommand::new(
+            &[
+                "cargo",
+                "clippy",
+                "--message-format=json",
+                "--manifest-path",
+                "{dir}/Cargo.toml",
+            ],
+            RustcJson,
+        ),
+        ("rust", Stage::Project) => CheckCommand::new(
+            &[
+                "cargo",
+                "check",
+                "--message-format=json",
+                "--manifest-path",
+                "{dir}/Cargo.toml",
+            ],
+            RustcJson,
+        ),
+        ("python", Stage::Syntax) => {
+            CheckCommand::new(&["python3", "-m", "py_compile", "{file}"], PyCompile)
+        }
+        ("python", Stage::Type) => CheckCommand::new(
+            &[
+                "mypy",
+                "--show-column-numbers",
+                "--show-error-codes",
+                "--no-error-summary",
+                "{file}",
+            ],
+            Mypy,
+        ),
+        ("python", Stage::Fix) => CheckCommand::new(&["black", "--quiet", "{file}"], None),
+        ("python", Stage::Lint) => CheckCommand::new(
+            &["ruff", "check", "--output-format", "json", "{file}"],
+            RuffJson,
+        ),
+        ("typescript", Stage::Syntax) => {
+            CheckCommand::new(&["tsc", "--noEmit", "--pretty", "false", "{file}"], Tsc)
+        }
+        ("typescript", Stage::Project) => CheckCommand::new(
+            &["tsc", "--noEmit", "--pretty", "false", "-p", "{dir}"],
+            Tsc,
+        ),
+        ("typescript", Stage::Lint) => {
+            CheckCommand::new(&["eslint", "--format", "json", "{file}"], Eslint)
+        }
+        // Read-only: go vet runs alongside it on the same file
+        ("go", Stage::Syntax) => CheckCommand::new(&["gofmt", "-l", "-e", "{file}"], Colon),
+        ("go", Stage::Fix) => CheckCommand::new(&["gofmt", "-w", "{file}"], None),
+        ("go", Stage::Lint) => CheckCommand::new(&["go", "vet", "{file}"], GoVet),
+        ("javascript", Stage::Syntax) => CheckCommand::new(&["node", "--check", "{file}"], None),
+        ("javascript", Stage::Lint) => {
+            CheckCommand::new(&["eslint", "--format", "json", "{file}"], Eslint)
+        }
+        ("nushell", Stage::Syntax) => CheckCommand::new(
+            // nu-check parses without running; the raw string keeps the path literal
+            &["nu", "--no-config-file", "-c", "nu-check --debug {file_nu}"],
+            NuCheck,
+        ),
+        ("bash", Stage::Syntax) => CheckCommand::new(&["bash", "-n", "{file}"], None),
+        ("sh", Stage::Syntax) => CheckCommand::new(&["sh", "-n", "{file}"], None),
+        ("bash", Stage::Lint) => CheckCommand::new(
+            &["shellcheck", "--format=json1", "--shell=bash", "{file}"],
+            Shellcheck,
+        ),
+        ("sh", Stage::Lint) => CheckCommand::new(
+            &["shellcheck", "--format=json1", "--shell=sh", "{file}"],
+            Shellcheck,
+        ),
+        _ => return Option::None,
+    };
+    Some(cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_overrides_default() {
+        let toml = r#"
+model = "ignored/by-gate1"
+
+[gate1]
+fail_on = "warning"
+
+[gate1.lint]
+level = "deny"
+
+[gate1.python.syntax]
+command = ["ruff", "check", "--output-format", "json", "{file}"]
+format = "ruff-json"
+"#;
+        let toolchain: Toolchain = bt_core::config::section_from_toml_str(toml, "gate1").unwrap();
+        let syntax = toolchain.command("python", Stage::Syntax).unwrap();
+        assert_eq!(syntax.program(), "ruff");
+        assert_eq!(syntax.format, OutputFormat::RuffJson);
+        assert_eq!(
+            toolchain.command("go", Stage::Syntax).unwrap().program(),
+            "gofmt"
+        );
+        assert_eq!(toolchain.lint.level, crate::lint::LintLevel::Deny);
+        assert_eq!(toolchain.fail_on, Some(FailOn::Warning));
+    }
+
+    #[test]
+    fn test_nushell_command_quotes_path() {
+        let toolchain = Toolchain::default();
+        let nu = toolchain.command("nushell", Stage::Syntax).unwrap();
+        assert_eq!(nu.format, OutputFormat::NuCheck);
+        let rendered = nu.render("/tmp/plain.nu", &[]);
+        assert_eq!(
+            rendered.last().unwrap(),
+            "nu-check --debug r#'/tmp/plain.nu'#"
+        );
+
+        // A quote followed by hashes would end a one-hash raw string early
+        let rendered = nu.render("/tmp/it's'#odd'##.nu", &[]);
+        assert_eq!(
+            rendered.last().unwrap(),
+            "nu-check --debug r###'/tmp/it's'#odd'##.nu'###"
+        );
+    }
+
+    #[test]
+    fn test_deadline_times_out_check_commands() {
+        let started = Instant::now();
+        set_deadline(Some(started + Duration::from_millis(300)));
+        let command = CheckCommand::new(&["sleep", "30"], OutputFormat::None);
+        let run = command.run("x", &[]).unwrap();
+        set_deadline(None);
+        assert!(run.timed_out);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
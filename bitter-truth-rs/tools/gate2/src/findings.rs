@@ -0,0 +1,234 @@
+// Structured security findings parsed from scanner output
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    fn parse(s: &str) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "critical" => Severity::Critical,
+            "high" | "error" => Severity::High,
+            "medium" | "moderate" | "warning" => Severity::Medium,
+            _ => Severity::Low,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    /// Tool that reported it: secrets, cargo-audit, cargo-deny, bandit, npm-audit
+    pub scanner: String,
+    pub severity: Severity,
+    /// Advisory id, bandit test id, or secret rule name
+    pub rule: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    /// Vulnerable dependency, for audit findings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+}
+
+impl Finding {
+    pub fn new(
+        scanner: &str,
+        severity: Severity,
+        rule: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            scanner: scanner.to_string(),
+            severity,
+            rule: rule.into(),
+            message: message.into(),
+            file: None,
+            line: None,
+            package: None,
+        }
+    }
+
+    pub fn at(mut self, file: impl Into<String>, line: u32) -> Self {
+        self.file = Some(file.into());
+        self.line = Some(line);
+        self
+    }
+
+    pub fn in_package(mut self, package: impl Into<String>) -> Self {
+        self.package = Some(package.into());
+        self
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, &self.package) {
+            (Some(file), _) => write!(f, "{}:{}: ", file, self.line.unwrap_or(0))?,
+            (None, Some(package)) => write!(f, "{}: ", package)?,
+            (None, None) => {}
+        }
+        let severity = serde_json::to_value(self.severity).ok();
+        let severity = severity.as_ref().and_then(|s| s.as_str()).unwrap_or("high");
+        write!(
+            f,
+            "{}[{}/{}]: {}",
+            severity, self.scanner, self.rule, self.message
+        )
+    }
+}
+
+/// `cargo audit --json`
+pub fn parse_cargo_audit(output: &str) -> Vec<Finding> {
+    let Ok(parsed) = serde_json::from_str::<serde_json::Value>(output) else {
+        return vec![];
+    };
+    parsed["vulnerabilities"]["list"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|v| {
+            let advisory = &v["advisory"];
+            let package = format!(
+                "{} {}",
+                v["package"]["name"].as_str().unwrap_or(""),
+                v["package"]["version"].as_str().unwrap_or("")
+            );
+            // Known vulnerabilities block shipping regardless of CVSS
+            let severity = advisory["severity"]
+                .as_str()
+                .map(Severity::parse)
+                .unwrap_or(Severity::High);
+            Finding::new(
+                "cargo-audit",
+                severity.max(Severity::High),
+                advisory["id"].as_str().unwrap_or(""),
+                advisory["title"].as_str().unwrap_or(""),
+            )
+            .in_package(package.trim())
+        })
+        .collect()
+}
+
+/// `cargo deny --format json check`: one JSON diagnostic per stderr line
+pub fn parse_cargo_deny(output: &str) -> Vec<Finding> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|v| v["type"] == "diagnostic")
+        .filter_map(|v| {
+            let fields = &v["fields"];
+            let severity = fields["severity"].as_str()?;
+            if !matches!(severity, "error" | "warning") {
+                return None;
+            }
+            Some(Finding::new(
+                "cargo-deny",
+                Severity::parse(severity),
+                fields["code"].as_str().unwrap_or(""),
+                fields["message"].as_str().unwrap_or(""),
+            ))
+        })
+        .collect()
+}
+
+/// `bandit -f json`
+pub fn parse_bandit(output: &str) -> Vec<Finding> {
+    let Ok(parsed) = serde_json::from_str::<serde_json::Value>(output) else {
+        return vec![];
+    };
+    parsed["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|r| {
+            Finding::new(
+                "bandit",
+                Severity::parse(r["issue_severity"].as_str().unwrap_or("low")),
+                r["test_id"].as_str().unwrap_or(""),
+                r["issue_text"].as_str().unwrap_or(""),
+            )
+            .at(
+                r["filename"].as_str().unwrap_or(""),
+                r["line_number"].as_u64().unwrap_or(0) as u32,
+            )
+        })
+        .collect()
+}
+
+/// `npm audit --json` (npm 7+)
+pub fn parse_npm_audit(output: &str) -> Vec<Finding> {
+    let Ok(parsed) = serde_json::from_str::<serde_json::Value>(output) else {
+        return vec![];
+    };
+    parsed["vulnerabilities"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, v)| {
+            // `via` holds advisory objects, or names of vulnerable dependencies
+            let advisory = v["via"]
+                .as_array()
+                .and_then(|via| via.iter().find(|a| a.is_object()));
+            let title = advisory
+                .and_then(|a| a["title"].as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| {
+                    format!(
+                        "Depends on a vulnerable package ({})",
+                        v["range"].as_str().unwrap_or("*")
+                    )
+                });
+            let rule = advisory
+                .and_then(|a| a["url"].as_str())
+                .and_then(|url| url.rsplit('/').next())
+                .unwrap_or("npm-advisory");
+            Finding::new(
+                "npm-audit",
+                Severity::parse(v["severity"].as_str().unwrap_or("low")),
+                rule,
+                title,
+            )
+            .in_package(name.as_str())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_audit() {
+        let out = r#"{"vulnerabilities":{"found":true,"count":1,"list":[{"advisory":{"id":"RUSTSEC-2020-0071","title":"Potential segfault in the time crate"},"package":{"name":"time","version":"0.1.45"}}]}}"#;
+        let findings = parse_cargo_audit(out);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].to_string(),
+            "time 0.1.45: high[cargo-audit/RUSTSEC-2020-0071]: Potential segfault in the time crate"
+        );
+    }
+
+    #[test]
+    fn test_parse_bandit_and_npm_audit() {
+        let bandit = r#"{"results":[{"filename":"app.py","line_number":4,"issue_severity":"HIGH","issue_text":"Use of exec detected.","test_id":"B102"}]}"#;
+        let findings = parse_bandit(bandit);
+        assert_eq!(findings[0].severity, Severity::High);
+        assert_eq!(findings[0].line, Some(4));
+
+        let npm = r#"{"vulnerabilities":{"minimist":{"name":"minimist","severity":"moderate","via":[{"title":"Prototype Pollution","url":"https://github.com/advisories/GHSA-xvch-5gv4-984h"}],"range":"<1.2.6"}}}"#;
+        let findings = parse_npm_audit(npm);
+        assert_eq!(findings[0].severity, Severity::Medium);
+        assert_eq!(findings[0].rule, "GHSA-xvch-5gv4-984h");
+        assert_eq!(findings[0].package.as_deref(), Some("minimist"));
+    }
+}
@@ -0,0 +1,319 @@
+mod findings;
+mod secrets;
+
+use bt_core::{log_stderr, BtError, Context, LogEntry, ToolError};
+use findings::{Finding, Severity};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Command, ExitCode};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Deserialize)]
+struct Gate2Input {
+    /// A generated file, or the generated project directory
+    code_path: String,
+    /// Lowest severity that fails the gate
+    #[serde(default = "default_fail_on")]
+    fail_on: Severity,
+    #[serde(default)]
+    context: Context,
+}
+
+fn default_fail_on() -> Severity {
+    Severity::Medium
+}
+
+#[derive(Debug, Serialize)]
+struct Gate2Output {
+    passed: bool,
+    findings: Vec<Finding>,
+    /// Scanners that ran
+    scanners: Vec<String>,
+    /// Scanners that applied but are not installed
+    skipped: Vec<String>,
+    /// Scanners killed at `context.timeout_seconds`
+    timed_out: Vec<String>,
+    was_dry_run: bool,
+}
+
+fn main() -> ExitCode {
+    bt_core::run(gate2)
+}
+
+fn gate2(input: Gate2Input) -> Result<Gate2Output, ToolError> {
+    let trace_id = input.context.trace_id.clone();
+
+    if input.code_path.is_empty() {
+        return Err(BtError::InvalidInput("code_path is required".to_string()).into());
+    }
+
+    if input.context.dry_run {
+        let log = LogEntry::info("dry-run mode - skipping security scan", trace_id.clone());
+        log_stderr(&log);
+
+        return Ok(Gate2Output {
+            passed: true,
+            findings: vec![],
+            scanners: vec![],
+            skipped: vec![],
+            timed_out: vec![],
+            was_dry_run: true,
+        });
+    }
+
+    let path = Path::new(&input.code_path);
+    if !path.exists() {
+        return Err(BtError::NotFound(format!("Code path not found: {}", input.code_path)).into());
+    }
+
+    // One deadline for the whole scan, as gate1 does for its check commands
+    let deadline = input
+        .context
+        .timeout_seconds
+        .map(|s| Instant::now() + Duration::from_secs(s));
+
+    let log = LogEntry::info("starting Gate 2 security scan", trace_id.clone()).with_extra(
+        "code_path",
+        serde_json::Value::String(input.code_path.clone()),
+    );
+    log_stderr(&log);
+
+    let mut output = Gate2Output {
+        passed: true,
+        findings: secrets::scan(path),
+        scanners: vec!["secrets".to_string()],
+        skipped: vec![],
+        timed_out: vec![],
+        was_dry_run: false,
+    };
+
+    for scanner in applicable_scanners(path) {
+        let log = LogEntry::debug("running scanner", trace_id.clone()).with_extra(
+            "scanner",
+            serde_json::Value::String(scanner.name.to_string()),
+        );
+        log_stderr(&log);
+
+        let timeout = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+        match scanner.run(timeout) {
+            ScanRun::Found(found) => {
+                output.scanners.push(scanner.name.to_string());
+                output.findings.extend(found);
+            }
+            ScanRun::TimedOut => {
+                let log = LogEntry::warn("scanner timed out", trace_id.clone()).with_extra(
+                    "scanner",
+                    serde_json::Value::String(scanner.name.to_string()),
+                );
+                log_stderr(&log);
+                output.timed_out.push(scanner.name.to_string());
+            }
+            ScanRun::Missing => {
+                let log = LogEntry::debug("scanner not available, skipping", trace_id.clone())
+                    .with_extra(
+                        "scanner",
+                        serde_json::Value::String(scanner.name.to_string()),
+                    );
+                log_stderr(&log);
+                output.skipped.push(scanner.name.to_string());
+            }
+        }
+    }
+
+    output
+        .findings
+        .sort_by_key(|f| std::cmp::Reverse(f.severity));
+    output.passed =
+        output.timed_out.is_empty() && !output.findings.iter().any(|f| f.severity >= input.fail_on);
+
+    let log = LogEntry::info("Gate 2 security scan complete", trace_id.clone())
+        .with_extra("passed", serde_json::Value::Bool(output.passed))
+        .with_extra("findings", serde_json::json!(output.findings.len()));
+    log_stderr(&log);
+
+    if output.passed {
+        return Ok(output);
+    }
+
+    let blocking: Vec<String> = output
+        .findings
+        .iter()
+        .filter(|f| f.severity >= input.fail_on)
+        .map(|f| f.to_string())
+        .collect();
+    // A scanner that did not finish cannot vouch for the code
+    let error = if output.timed_out.is_empty() {
+        BtError::GateFailed(format!(
+            "Gate 2 security scan failed: {}",
+            blocking.join("; ")
+        ))
+    } else {
+        BtError::Timeout(format!(
+            "Gate 2 scanners timed out: {}",
+            output.timed_out.join(", ")
+        ))
+    };
+    Err(ToolError::from(error)
+        .with_detail("findings", serde_json::json!(output.findings))
+        .with_detail("scanners", serde_json::json!(output.scanners))
+        .with_detail("skipped", serde_json::json!(output.skipped))
+        .with_detail("timed_out", serde_json::json!(output.timed_out)))
+}
+
+/// Outcome of running one external scanner
+enum ScanRun {
+    Found(Vec<Finding>),
+    /// Not installed
+    Missing,
+    /// Killed at the timeout; its report is discarded
+    TimedOut,
+}
+
+/// An external scanner and how to read its report
+struct Scanner {
+    name: &'static str,
+    command: Vec<String>,
+    parse: fn(&str) -> Vec<Finding>,
+}
+
+impl Scanner {
+    fn new(name: &'static str, command: &[&str], parse: fn(&str) -> Vec<Finding>) -> Self {
+        Self {
+            name,
+            command: command.iter().map(|s| s.to_string()).collect(),
+            parse,
+        }
+    }
+
+    /// Run the scanner, killing its whole process group if it outlives `timeout`
+    fn run(&self, timeout: Option<Duration>) -> ScanRun {
+        let Some((program, args)) = self.command.split_first() else {
+            return ScanRun::Missing;
+        };
+        let mut command = Command::new(program);
+        command.args(args);
+        let output = match bt_core::process::output_with_timeout(&mut command, timeout) {
+            Ok(Some(output)) => output,
+            Ok(None) => return ScanRun::TimedOut,
+            Err(_) => return ScanRun::Missing,
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // `cargo <sub>` spawns fine even when the subcommand is missing
+        if stderr.contains("no such command") {
+            return ScanRun::Missing;
+        }
+        let mut found = (self.parse)(&stdout);
+        found.extend((self.parse)(&stderr));
+        ScanRun::Found(found)
+    }
+}
+
+/// Dependency and code scanners that apply to what was generated
+fn applicable_scanners(path: &Path) -> Vec<Scanner> {
+    let dir = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(Path::new("."))
+    };
+    let at = |name: &str| dir.join(name).display().to_string();
+    let mut scanners = vec![];
+
+    if dir.join("Cargo.lock").exists() {
+        scanners.push(Scanner::new(
+            "cargo-audit",
+            &["cargo", "audit", "--json", "--file", &at("Cargo.lock")],
+            findings::parse_cargo_audit,
+        ));
+    }
+    if dir.join("Cargo.toml").exists() {
+        // Without a deny.toml only advisories are meaningful; license checks would reject everything
+        let checks: &[&str] = if dir.join("deny.toml").exists() {
+            &["check"]
+        } else {
+            &["check", "advisories"]
+        };
+        let manifest = at("Cargo.toml");
+        let mut command = vec![
+            "cargo",
+            "deny",
+            "--manifest-path",
+            &manifest,
+            "--format",
+            "json",
+        ];
+        command.extend_from_slice(checks);
+        scanners.push(Scanner::new(
+            "cargo-deny",
+            &command,
+            findings::parse_cargo_deny,
+        ));
+    }
+
+    if secrets::files(path)
+        .iter()
+        .any(|f| f.extension().is_some_and(|x| x == "py"))
+    {
+        let target = path.display().to_string();
+        scanners.push(Scanner::new(
+            "bandit",
+            &["bandit", "-f", "json", "-q", "-r", &target],
+            findings::parse_bandit,
+        ));
+    }
+
+    if dir.join("package-lock.json").exists() {
+        scanners.push(Scanner::new(
+            "npm-audit",
+            &[
+                "npm",
+                "audit",
+                "--json",
+                "--prefix",
+                &dir.display().to_string(),
+            ],
+            findings::parse_npm_audit,
+        ));
+    }
+
+    scanners
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scanner_timeout_and_missing_scanner() {
+        let started = Instant::now();
+        let scanner = Scanner::new("slow", &["sleep", "30"], findings::parse_bandit);
+        assert!(matches!(
+            scanner.run(Some(Duration::from_millis(300))),
+            ScanRun::TimedOut
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let missing = Scanner::new("missing", &["/nonexistent/scanner"], findings::parse_bandit);
+        assert!(matches!(missing.run(None), ScanRun::Missing));
+    }
+
+    #[test]
+    fn test_python_found_in_subdirectories_only_outside_venvs() {
+        let root = std::env::temp_dir().join(format!("bt-gate2-scanners-{}", std::process::id()));
+        std::fs::create_dir_all(root.join(".venv/lib")).unwrap();
+        std::fs::write(root.join(".venv/lib/site.py"), "").unwrap();
+        let names = |root: &Path| {
+            applicable_scanners(root)
+                .iter()
+                .map(|s| s.name)
+                .collect::<Vec<_>>()
+        };
+        assert!(names(&root).is_empty());
+
+        std::fs::create_dir_all(root.join("src/app")).unwrap();
+        std::fs::write(root.join("src/app/main.py"), "print('hi')\n").unwrap();
+        assert_eq!(names(&root), vec!["bandit"]);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,260 @@
+// Native Anthropic Messages API backend
+
+use crate::progress::Progress;
+use crate::provider::{CallSettings, Generation, GenerationParams, Params, Provider};
+use bt_core::usage::Usage;
+use bt_core::{log_stderr, BtError, LogEntry};
+use serde::Deserialize;
+
+const API_VERSION: &str = "2023-06-01";
+const DEFAULT_MAX_TOKENS: u32 = 8192;
+
+/// `[generate.anthropic]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AnthropicConfig {
+    /// API root; `/v1/messages` is appended
+    pub base_url: String,
+    /// Environment variable holding the API key
+    pub api_key_env: String,
+    /// Overrides the global `model` for this provider
+    pub model: Option<String>,
+    /// Sampling defaults; `max_tokens` is required by the API (8192 unless set)
+    /// and a reply that hits it is treated as truncated. `seed` is not supported.
+    #[serde(flatten)]
+    pub defaults: GenerationParams,
+    pub stop_sequences: Vec<String>,
+    /// Stream the reply over SSE so long generations don't sit on an idle connection
+    pub stream: bool,
+    #[serde(flatten)]
+    pub call: CallSettings,
+}
+
+impl Default for AnthropicConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.anthropic.com".to_string(),
+            api_key_env: "ANTHROPIC_API_KEY".to_string(),
+            model: None,
+            defaults: GenerationParams {
+                max_tokens: Some(DEFAULT_MAX_TOKENS),
+                ..GenerationParams::default()
+            },
+            stop_sequences: vec![],
+            stream: true,
+            call: CallSettings::default(),
+        }
+    }
+}
+
+/// A finished reply, however it was delivered
+#[derive(Debug, Default)]
+struct Reply {
+    text: String,
+    stop_reason: Option<String>,
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+impl Reply {
+    /// Build from a non-streamed Messages response
+    fn from_message(message: &serde_json::Value) -> Self {
+        let text = message["content"]
+            .as_array()
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .filter_map(|b| b["text"].as_str())
+                    .collect::<String>()
+            })
+            .unwrap_or_default();
+        Self {
+            text,
+            stop_reason: message["stop_reason"].as_str().map(str::to_string),
+            input_tokens: message["usage"]["input_tokens"].as_u64().unwrap_or(0),
+            output_tokens: message["usage"]["output_tokens"].as_u64().unwrap_or(0),
+        }
+    }
+
+    /// Fold one SSE `data:` payload into the reply
+    fn apply_event(&mut self, data: &str) -> Result<(), BtError> {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(data) else {
+            return Ok(());
+        };
+        match event["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                self.input_tokens = event["message"]["usage"]["input_tokens"]
+                    .as_u64()
+                    .unwrap_or(0);
+            }
+            "content_block_delta" => {
+                if let Some(text) = event["delta"]["text"].as_str() {
+                    self.text.push_str(text);
+                }
+            }
+            "message_delta" => {
+                if let Some(reason) = event["delta"]["stop_reason"].as_str() {
+                    self.stop_reason = Some(reason.to_string());
+                }
+                if let Some(tokens) = event["usage"]["output_tokens"].as_u64() {
+                    self.output_tokens = tokens;
+                }
+            }
+            "error" => {
+                let message = event["error"]["message"]
+                    .as_str()
+                    .unwrap_or("unknown error");
+                // Overloaded and similar mid-stream errors are transient
+                return Err(BtError::Io(format!("Anthropic stream error: {}", message)));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Provider for AnthropicConfig {
+    fn name(&self) -> &'static str {
+        "anthropic"
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    fn settings(&self) -> &CallSettings {
+        &self.call
+    }
+
+    fn defaults(&self) -> &GenerationParams {
+        &self.defaults
+    }
+
+    /// Send `prompt` as a single user message and return the reply text
+    fn generate(&self, prompt: &str, params: &Params) -> Result<Generation, BtError> {
+        let url = format!("{}/v1/messages", self.base_url.trim_end_matches('/'));
+        // The shared default model uses opencode's provider/model form
+        let model = params
+            .model
+            .strip_prefix("anthropic/")
+            .unwrap_or(params.model);
+
+        let api_key = std::env::var(&self.api_key_env)
+            .ok()
+            .filter(|k| !k.is_empty())
+            .ok_or_else(|| {
+                BtError::DependencyMissing(format!("{} is not set", self.api_key_env))
+            })?;
+
+        let sampling = &params.sampling;
+        let max_tokens = sampling.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+        let mut body = serde_json::json!({
+            "model": model,
+            "max_tokens": max_tokens,
+            "messages": [{"role": "user", "content": prompt}],
+            "stream": self.stream,
+        });
+        if let Some(temperature) = sampling.temperature {
+            body["temperature"] = temperature.into();
+        }
+        if let Some(top_p) = sampling.top_p {
+            body["top_p"] = top_p.into();
+        }
+        if !self.stop_sequences.is_empty() {
+            body["stop_sequences"] = serde_json::json!(self.stop_sequences);
+        }
+
+        let log = LogEntry::info(
+            "calling Anthropic Messages API",
+            params.trace_id.to_string(),
+        )
+        .with_extra("model", serde_json::Value::String(model.to_string()))
+        .with_extra("max_tokens", serde_json::json!(max_tokens))
+        .with_extra("stream", serde_json::Value::Bool(self.stream));
+        log_stderr(&log);
+
+        let reply = crate::http::block_on(async {
+            let client = reqwest::Client::builder()
+                .timeout(params.timeout)
+                .build()
+                .map_err(|e| BtError::Internal(format!("HTTP client: {}", e)))?;
+            let mut response = client
+                .post(&url)
+                .header("x-api-key", &api_key)
+                .header("anthropic-version", API_VERSION)
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| crate::http::send_error(&url, e))?;
+
+            if !self.stream || !response.status().is_success() {
+                let message = crate::http::json_body(response, &url).await?;
+                return Ok(Reply::from_message(&message));
+            }
+
+            let mut reply = Reply::default();
+            let mut progress = Progress::new("anthropic", params);
+            crate::http::for_each_line(&mut response, &url, |line| {
+                if let Some(data) = line.strip_prefix("data:") {
+                    let before = reply.text.len();
+                    reply.apply_event(data.trim_start())?;
+                    progress.push(&reply.text[before..]);
+                }
+                Ok(())
+            })
+            .await?;
+            progress.finish();
+            Ok::<_, BtError>(reply)
+        })?;
+
+        let log = LogEntry::debug("Anthropic reply complete", params.trace_id.to_string())
+            .with_extra("stop_reason", serde_json::json!(reply.stop_reason))
+            .with_extra("output_tokens", serde_json::json!(reply.output_tokens));
+        log_stderr(&log);
+
+        // Truncated code only fails later gates in confusing ways, so stop here
+        if reply.stop_reason.as_deref() == Some("max_tokens") {
+            return Err(BtError::Internal(format!(
+                "Reply truncated at max_tokens ({}); raise params.max_tokens or [generate.anthropic] max_tokens",
+                max_tokens
+            )));
+        }
+        Ok(Generation {
+            text: reply.text,
+            usage: Usage {
+                prompt_tokens: reply.input_tokens,
+                completion_tokens: reply.output_tokens,
+                cost_usd: 0.0,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_events_accumulate_reply() {
+        let events = [
+            r#"{"type":"message_start","message":{"usage":{"input_tokens":12,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"fn main() "}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"{}"}}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":7}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let mut reply = Reply::default();
+        for event in events {
+            reply.apply_event(event).unwrap();
+        }
+        assert_eq!(reply.text, "fn main() {}");
+        assert_eq!(reply.stop_reason.as_deref(), Some("end_turn"));
+        assert_eq!((reply.input_tokens, reply.output_tokens), (12, 7));
+
+        let err = reply.apply_event(
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+        );
+        assert!(matches!(err, Err(BtError::Io(_))));
+    }
+}
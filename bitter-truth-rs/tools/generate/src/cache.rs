@@ -0,0 +1,152 @@
+// Content-addressed response cache, so identical retries don't re-bill the model
+
+use crate::provider::{Generation, GenerationParams};
+use bt_core::usage::Usage;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `[generate.cache]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Off by default: a hit replays the earlier reply even when sampling or
+    /// a retry is meant to produce a different one
+    pub enabled: bool,
+    /// Defaults to `<output_dir>/bt-generate-cache`
+    pub dir: Option<String>,
+    pub ttl_seconds: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            ttl_seconds: 24 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    created_at: u64,
+    text: String,
+    usage: Usage,
+}
+
+pub struct Cache {
+    dir: PathBuf,
+    ttl_seconds: u64,
+}
+
+impl Cache {
+    pub fn new(config: &CacheConfig, output_dir: &str) -> Self {
+        Self {
+            dir: config
+                .dir
+                .as_ref()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(output_dir).join("bt-generate-cache")),
+            ttl_seconds: config.ttl_seconds,
+        }
+    }
+
+    /// sha256 over everything that shapes the reply
+    pub fn key(provider: &str, model: &str, sampling: &GenerationParams, prompt: &str) -> String {
+        let sampling = serde_json::to_string(sampling).unwrap_or_default();
+        let mut hasher = Sha256::new();
+        for part in [provider, model, &sampling, prompt] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// A fresh cached reply; expired or unreadable entries count as misses
+    pub fn get(&self, key: &str) -> Option<Generation> {
+        let content = std::fs::read_to_string(self.path(key)).ok()?;
+        let entry: Entry = serde_json::from_str(&content).ok()?;
+        if now().saturating_sub(entry.created_at) > self.ttl_seconds {
+            return None;
+        }
+        Some(Generation {
+            text: entry.text,
+            usage: entry.usage,
+        })
+    }
+
+    /// Best effort: a cache that can't be written just means the next run misses
+    pub fn put(&self, key: &str, generation: &Generation) {
+        let entry = Entry {
+            created_at: now(),
+            text: generation.text.clone(),
+            usage: generation.usage,
+        };
+        let _ = std::fs::create_dir_all(&self.dir).and_then(|_| {
+            std::fs::write(
+                self.path(key),
+                serde_json::to_string(&entry).unwrap_or_default(),
+            )
+        });
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_round_trip_and_expiry() {
+        let dir = std::env::temp_dir().join(format!("generate-cache-{}", std::process::id()));
+        let config = CacheConfig {
+            dir: Some(dir.display().to_string()),
+            ..CacheConfig::default()
+        };
+        let cache = Cache::new(&config, "/unused");
+        let sampling = GenerationParams::default();
+        let key = Cache::key("openai", "gpt-4o", &sampling, "prompt");
+        assert_ne!(key, Cache::key("openai", "gpt-4o", &sampling, "prompt "));
+        let seeded = GenerationParams {
+            seed: Some(1),
+            ..GenerationParams::default()
+        };
+        assert_ne!(key, Cache::key("openai", "gpt-4o", &seeded, "prompt"));
+        assert!(cache.get(&key).is_none());
+
+        cache.put(
+            &key,
+            &Generation {
+                text: "fn main() {}".to_string(),
+                ..Generation::default()
+            },
+        );
+        assert_eq!(cache.get(&key).unwrap().text, "fn main() {}");
+
+        let expired = Cache {
+            dir: dir.clone(),
+            ttl_seconds: 0,
+        };
+        let entry = Entry {
+            created_at: now() - 10,
+            text: String::new(),
+            usage: Usage::default(),
+        };
+        std::fs::write(expired.path(&key), serde_json::to_string(&entry).unwrap()).unwrap();
+        assert!(expired.get(&key).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+// Token counting and per-model cost estimates
+
+use bt_core::usage::Usage;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Price {
+    pub input: f64,
+    pub output: f64,
+}
+
+/// Built-in list prices, matched by substring against the model name; first match wins
+const PRICES: &[(&str, Price)] = &[
+    (
+        "claude-opus-4-5",
+        Price {
+            input: 5.0,
+            output: 25.0,
+        },
+    ),
+    (
+        "opus",
+        Price {
+            input: 15.0,
+            output: 75.0,
+        },
+    ),
+    (
+        "sonnet",
+        Price {
+            input: 3.0,
+            output: 15.0,
+        },
+    ),
+    (
+        "haiku-4",
+        Price {
+            input: 1.0,
+            output: 5.0,
+        },
+    ),
+    (
+        "haiku",
+        Price {
+            input: 0.8,
+            output: 4.0,
+        },
+    ),
+    (
+        "gpt-4o-mini",
+        Price {
+            input: 0.15,
+            output: 0.6,
+        },
+    ),
+    (
+        "gpt-4o",
+        Price {
+            input: 2.5,
+            output: 10.0,
+        },
+    ),
+    (
+        "gpt-4.1-mini",
+        Price {
+            input: 0.4,
+            output: 1.6,
+        },
+    ),
+    (
+        "gpt-4.1",
+        Price {
+            input: 2.0,
+            output: 8.0,
+        },
+    ),
+];
+
+/// Count tokens the way OpenAI's tokenizers do; close enough for other vendors' models
+pub fn count_tokens(model: &str, text: &str) -> u64 {
+    let bpe = if ["gpt-4o", "gpt-4.1", "gpt-5", "o1", "o3", "o4"]
+        .iter()
+        .any(|m| model.contains(m))
+    {
+        tiktoken_rs::o200k_base_singleton()
+    } else {
+        tiktoken_rs::cl100k_base_singleton()
+    };
+    bpe.encode_ordinary(text).len() as u64
+}
+
+/// Price for `model`: `[generate.pricing]` entries first, then the built-in list.
+/// Local providers cost nothing.
+pub fn price_for(provider: &str, model: &str, overrides: &HashMap<String, Price>) -> Option<Price> {
+    if provider == "ollama" {
+        return Some(Price {
+            input: 0.0,
+            output: 0.0,
+        });
+    }
+    let model = model.to_lowercase();
+    overrides
+        .iter()
+        .filter(|(name, _)| model.contains(&name.to_lowercase()))
+        .max_by_key(|(name, _)| name.len())
+        .map(|(_, price)| *price)
+        .or_else(|| {
+            PRICES
+                .iter()
+                .find(|(name, _)| model.contains(name))
+                .map(|(_, price)| *price)
+        })
+}
+
+/// Fill in what the provider didn't report. Returns the completed usage and
+/// whether the token counts are local estimates.
+pub fn complete(
+    reported: Usage,
+    prompt: &str,
+    reply: &str,
+    model: &str,
+    price: Option<Price>,
+) -> (Usage, bool) {
+    let mut usage = reported;
+    let estimated = usage.total_tokens() == 0;
+    if estimated {
+        usage.prompt_tokens = count_tokens(model, prompt);
+        usage.completion_tokens = count_tokens(model, reply);
+    }
+    if usage.cost_usd == 0.0 {
+        if let Some(price) = price {
+            usage.cost_usd = (usage.prompt_tokens as f64 * price.input
+                + usage.completion_tokens as f64 * price.output)
+                / 1_000_000.0;
+        }
+    }
+    (usage, estimated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_estimates_missing_counts_and_prices_them() {
+        let sonnet = price_for("opencode", "anthropic/claude-sonnet-4-5", &HashMap::new());
+        assert_eq!(
+            sonnet,
+            Some(Price {
+                input: 3.0,
+                output: 15.0
+            })
+        );
+
+        let reported = Usage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 100_000,
+            cost_usd: 0.0,
+        };
+        let (usage, estimated) = complete(reported, "", "", "claude-sonnet-4-5", sonnet);
+        assert!(!estimated);
+        assert!((usage.cost_usd - 4.5).abs() < 1e-9);
+
+        let (usage, estimated) = complete(
+            Usage::default(),
+            "hello world",
+            "fn main() {}",
+            "gpt-4o",
+            None,
+        );
+        assert!(estimated);
+        assert_eq!(usage.prompt_tokens, 2);
+        assert!(usage.completion_tokens > 0);
+        assert_eq!(usage.cost_usd, 0.0);
+
+        let overrides = HashMap::from([(
+            "my-model".to_string(),
+            Price {
+                input: 1.0,
+                output: 1.0,
+            },
+        )]);
+        assert_eq!(
+            price_for("openai", "org/My-Model-7b", &overrides),
+            Some(Price {
+                input: 1.0,
+                output: 1.0
+            })
+        );
+        assert_eq!(price_for("openai", "unknown", &overrides), None);
+    }
+}
@@ -0,0 +1,155 @@
+// Edit mode: the model returns a unified diff against the existing file, which is applied here
+
+use bt_core::BtError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// How the output file is produced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Regenerate the whole file
+    #[default]
+    Full,
+    /// Send the current file and apply the unified diff the model returns
+    Edit,
+}
+
+/// The diff in a reply: the first ```diff/```patch fence, else the reply itself
+/// when it looks like a bare diff
+pub fn extract_diff(raw: &str) -> Result<String, BtError> {
+    let fence = Regex::new(r"(?ms)^```(?:diff|patch|udiff)[^\n]*\n(.*?)^```").unwrap();
+    let diff = match fence.captures(raw) {
+        Some(c) => c[1].to_string(),
+        None if raw.lines().any(|l| l.starts_with("@@ ")) => raw.to_string(),
+        None => {
+            return Err(BtError::ContractViolation(
+                "Reply contains no unified diff".to_string(),
+            ))
+        }
+    };
+    Ok(recount(&diff))
+}
+
+/// Apply `diff` to `original`. A diff that doesn't parse, doesn't match the file,
+/// or changes nothing is a contract violation, so the loop can ask again.
+pub fn apply(original: &str, diff: &str) -> Result<(String, usize), BtError> {
+    let patch = diffy::Patch::from_str(diff)
+        .map_err(|e| BtError::ContractViolation(format!("Malformed diff: {}", e)))?;
+    let hunks = patch.hunks().len();
+    if hunks == 0 {
+        return Err(BtError::ContractViolation("Diff has no hunks".to_string()));
+    }
+    let patched = diffy::apply(original, &patch).map_err(|e| {
+        BtError::ContractViolation(format!("Diff does not apply to the current file: {}", e))
+    })?;
+    if patched == original {
+        return Err(BtError::ContractViolation(
+            "Diff leaves the file unchanged".to_string(),
+        ));
+    }
+    Ok((patched, hunks))
+}
+
+/// A hunk header being rewritten: its index in the output, start lines and counted lengths
+struct Hunk {
+    at: usize,
+    old_start: String,
+    new_start: String,
+    rest: String,
+    old: usize,
+    new: usize,
+}
+
+impl Hunk {
+    fn close(self, out: &mut [String]) {
+        out[self.at] = format!(
+            "@@ -{},{} +{},{} @@{}",
+            self.old_start, self.old, self.new_start, self.new, self.rest
+        );
+    }
+}
+
+/// Models routinely miscount hunk lengths and drop the space on blank context
+/// lines; rewrite each `@@` header from the lines that actually follow it
+fn recount(diff: &str) -> String {
+    let header = Regex::new(r"^@@ -(\d+)(?:,\d+)? \+(\d+)(?:,\d+)? @@(.*)$").unwrap();
+    let mut out: Vec<String> = Vec::new();
+    let mut hunk: Option<Hunk> = None;
+
+    for line in diff.lines() {
+        if let Some(c) = header.captures(line) {
+            if let Some(done) = hunk.take() {
+                done.close(&mut out);
+            }
+            hunk = Some(Hunk {
+                at: out.len(),
+                old_start: c[1].to_string(),
+                new_start: c[2].to_string(),
+                rest: c[3].to_string(),
+                old: 0,
+                new: 0,
+            });
+            out.push(line.to_string());
+            continue;
+        }
+        let Some(current) = hunk.as_mut() else {
+            out.push(line.to_string());
+            continue;
+        };
+        match line.chars().next() {
+            Some('-') => current.old += 1,
+            Some('+') => current.new += 1,
+            Some(' ') | None => {
+                current.old += 1;
+                current.new += 1;
+            }
+            Some('\\') => {}
+            _ => {
+                // Trailing prose or the next file's preamble ends the hunk
+                if let Some(done) = hunk.take() {
+                    done.close(&mut out);
+                }
+            }
+        }
+        out.push(if line.is_empty() {
+            " ".to_string()
+        } else {
+            line.to_string()
+        });
+    }
+    if let Some(done) = hunk {
+        done.close(&mut out);
+    }
+
+    let mut text = out.join("\n");
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_fenced_diff_with_miscounted_hunk() {
+        let original = "fn add(a: i32, b: i32) -> i32 {\n    a - b\n}\n\nfn main() {}\n";
+        let reply = "Fix:\n```diff\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,9 +1,9 @@\n fn add(a: i32, b: i32) -> i32 {\n-    a - b\n+    a + b\n }\n\n fn main() {}\n```\n";
+        let diff = extract_diff(reply).unwrap();
+        assert!(diff.contains("@@ -1,5 +1,5 @@"));
+        let (patched, hunks) = apply(original, &diff).unwrap();
+        assert_eq!(patched, original.replace("a - b", "a + b"));
+        assert_eq!(hunks, 1);
+
+        let stale = "@@ -1,1 +1,1 @@\n-fn nope() {}\n+fn yes() {}\n";
+        assert!(matches!(
+            apply(original, stale),
+            Err(BtError::ContractViolation(_))
+        ));
+        assert!(extract_diff("fn main() {}").is_err());
+
+        // An empty file takes a diff that only adds lines
+        let (patched, _) = apply("", "@@ -0,0 +1,1 @@\n+fn main() {}\n").unwrap();
+        assert_eq!(patched, "fn main() {}\n");
+    }
+}
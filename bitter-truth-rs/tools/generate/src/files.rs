@@ -0,0 +1,149 @@
+// Multi-file responses: annotated fenced blocks or a JSON manifest, written as a tree
+
+use bt_core::BtError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+
+/// One file the model produced
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GeneratedFile {
+    pub path: String,
+    pub content: String,
+}
+
+/// What was written, as reported in GenerateOutput
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub bytes: usize,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    files: Vec<GeneratedFile>,
+}
+
+/// Files in a response: a JSON `{"files": [{"path", "content"}]}` manifest (bare or
+/// fenced), else every fenced block whose first line is a `file: <path>` comment.
+/// Empty when the response is a plain single-file answer.
+pub fn parse(raw: &str) -> Vec<GeneratedFile> {
+    let fence = Regex::new(r"(?ms)^```([^\n`]*)\n(.*?)^```").unwrap();
+    let annotation = Regex::new(r"^\s*(?://|#|--)\s*file:\s*(\S+)\s*$").unwrap();
+
+    let candidates = std::iter::once(raw.trim()).chain(
+        fence
+            .captures_iter(raw)
+            .filter(|c| c[1].trim() == "json")
+            .map(|c| c.get(2).map_or("", |m| m.as_str())),
+    );
+    for candidate in candidates {
+        if let Ok(manifest) = serde_json::from_str::<Manifest>(candidate) {
+            return manifest.files;
+        }
+    }
+
+    fence
+        .captures_iter(raw)
+        .filter_map(|c| {
+            let body = c.get(2)?.as_str();
+            let (first, rest) = body.split_once('\n').unwrap_or((body, ""));
+            let path = annotation.captures(first)?[1].to_string();
+            Some(GeneratedFile {
+                path,
+                content: rest.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Write `files` under `dir`. Paths must stay inside `dir`; a model that tries
+/// otherwise gets a retryable contract violation rather than a write outside the tree.
+pub fn write_tree(dir: &Path, files: &[GeneratedFile]) -> Result<Vec<ManifestEntry>, BtError> {
+    for file in files {
+        let path = Path::new(&file.path);
+        if file.path.is_empty()
+            || !path
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(BtError::ContractViolation(format!(
+                "Generated file path must be relative and stay inside the output directory: {:?}",
+                file.path
+            )));
+        }
+    }
+
+    let mut manifest = Vec::with_capacity(files.len());
+    for file in files {
+        let target = dir.join(&file.path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                BtError::Io(format!("Failed to create {}: {}", parent.display(), e))
+            })?;
+        }
+        std::fs::write(&target, &file.content)
+            .map_err(|e| BtError::Io(format!("Failed to write {}: {}", target.display(), e)))?;
+        manifest.push(ManifestEntry {
+            path: file.path.clone(),
+            bytes: file.content.len(),
+        });
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_annotated_blocks_and_manifest() {
+        let raw = "Here you go:\n```rust\n// file: src/main.rs\nfn main() {}\n```\n\n```toml\n# file: Cargo.toml\n[package]\n```\n```rust\nfn stray() {}\n```\n";
+        assert_eq!(
+            parse(raw),
+            vec![
+                GeneratedFile {
+                    path: "src/main.rs".to_string(),
+                    content: "fn main() {}\n".to_string()
+                },
+                GeneratedFile {
+                    path: "Cargo.toml".to_string(),
+                    content: "[package]\n".to_string()
+                },
+            ]
+        );
+
+        let raw = "```json\n{\"files\": [{\"path\": \"a.py\", \"content\": \"print(1)\\n\"}]}\n```";
+        assert_eq!(
+            parse(raw),
+            vec![GeneratedFile {
+                path: "a.py".to_string(),
+                content: "print(1)\n".to_string()
+            }]
+        );
+
+        assert!(parse("```rust\nfn main() {}\n```").is_empty());
+    }
+
+    #[test]
+    fn test_write_tree_rejects_escaping_paths() {
+        let dir = std::env::temp_dir().join(format!("generate-files-{}", std::process::id()));
+        let escape = GeneratedFile {
+            path: "../evil.rs".to_string(),
+            content: String::new(),
+        };
+        assert!(matches!(
+            write_tree(&dir, &[escape]),
+            Err(BtError::ContractViolation(_))
+        ));
+
+        let file = GeneratedFile {
+            path: "src/lib.rs".to_string(),
+            content: "pub fn f() {}\n".to_string(),
+        };
+        let manifest = write_tree(&dir, &[file]).unwrap();
+        assert_eq!(manifest[0].bytes, 14);
+        assert!(dir.join("src/lib.rs").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+// Commit each generation to git so loop iterations are diffable and revertible
+
+use bt_core::BtError;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where generated code gets committed
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitOptions {
+    /// Repository to commit in
+    pub repo: String,
+    /// Write into a worktree at this path instead of the repo's own checkout;
+    /// created on `branch` if missing, reusing the branch as-is when it exists
+    #[serde(default)]
+    pub worktree: Option<String>,
+    /// Branch for a new worktree; defaults to `bt/<trace_id>`
+    #[serde(default)]
+    pub branch: Option<String>,
+}
+
+/// The directory to write into and commit from, creating the worktree if needed
+pub fn prepare(options: &GitOptions, trace_id: &str) -> Result<PathBuf, BtError> {
+    let repo = Path::new(&options.repo);
+    git(repo, &["rev-parse", "--git-dir"]).map_err(|e| {
+        BtError::InvalidInput(format!("Not a git repository: {} ({})", options.repo, e))
+    })?;
+
+    let Some(worktree) = &options.worktree else {
+        return absolute(repo);
+    };
+    if !Path::new(worktree).exists() {
+        let branch = options
+            .branch
+            .clone()
+            .unwrap_or_else(|| format!("bt/{}", trace_id));
+        // Never reset an existing branch: it may hold earlier attempts' commits
+        let exists = git(
+            repo,
+            &[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("refs/heads/{}", branch),
+            ],
+        )
+        .is_ok();
+        if exists {
+            git(repo, &["worktree", "add", worktree, &branch])?;
+        } else {
+            git(repo, &["worktree", "add", "-b", &branch, worktree])?;
+        }
+    }
+    absolute(Path::new(worktree))
+}
+
+/// Stage `paths` and commit only them, leaving anything else already staged
+/// alone; `None` when they match what is already committed
+pub fn commit(workdir: &Path, paths: &[PathBuf], message: &str) -> Result<Option<String>, BtError> {
+    let paths: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+    let with_paths = |args: &[&'static str]| -> Vec<&str> {
+        let mut args = args.to_vec();
+        args.extend(paths.iter().map(String::as_str));
+        args
+    };
+    git(workdir, &with_paths(&["add", "--"]))?;
+
+    if git(workdir, &with_paths(&["diff", "--cached", "--quiet", "--"])).is_ok() {
+        return Ok(None);
+    }
+
+    // Fall back to a tool identity on hosts (containers, CI) with none configured
+    let mut args = vec![];
+    if git(workdir, &["config", "user.email"]).is_err() {
+        args.extend([
+            "-c",
+            "user.name=bitter-truth",
+            "-c",
+            "user.email=bitter-truth@localhost",
+        ]);
+    }
+    args.extend(["commit", "--quiet", "-m", message, "--only", "--"]);
+    args.extend(paths.iter().map(String::as_str));
+    git(workdir, &args)?;
+    git(workdir, &["rev-parse", "HEAD"]).map(Some)
+}
+
+/// Resolve an output path against the workdir and make sure it lands inside it
+pub fn within(workdir: &Path, path: &str) -> Result<PathBuf, BtError> {
+    let resolved = if Path::new(path).is_absolute() {
+        PathBuf::from(path)
+    } else {
+        workdir.join(path)
+    };
+    let escapes = resolved
+        .components()
+        .any(|c| c == std::path::Component::ParentDir);
+    if escapes || !resolved.starts_with(workdir) {
+        return Err(BtError::InvalidInput(format!(
+            "Output {} is outside the git workdir {}",
+            resolved.display(),
+            workdir.display()
+        )));
+    }
+    Ok(resolved)
+}
+
+fn absolute(path: &Path) -> Result<PathBuf, BtError> {
+    std::fs::canonicalize(path)
+        .map_err(|e| BtError::Io(format!("Failed to resolve {}: {}", path.display(), e)))
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, BtError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| BtError::DependencyMissing(format!("git not available: {}", e)))?;
+    if !output.status.success() {
+        return Err(BtError::Io(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_in_new_worktree() {
+        let root = std::env::temp_dir().join(format!("generate-git-{}", std::process::id()));
+        let repo = root.join("repo");
+        std::fs::create_dir_all(&repo).unwrap();
+        git(&repo, &["init", "--quiet"]).unwrap();
+        git(
+            &repo,
+            &[
+                "-c",
+                "user.name=t",
+                "-c",
+                "user.email=t@t",
+                "commit",
+                "--quiet",
+                "--allow-empty",
+                "-m",
+                "init",
+            ],
+        )
+        .unwrap();
+
+        let options = GitOptions {
+            repo: repo.display().to_string(),
+            worktree: Some(root.join("wt").display().to_string()),
+            branch: None,
+        };
+        let workdir = prepare(&options, "abc123").unwrap();
+        assert_eq!(
+            git(&workdir, &["branch", "--show-current"]).unwrap(),
+            "bt/abc123"
+        );
+        assert!(within(&workdir, "/elsewhere/x.rs").is_err());
+        assert!(within(&workdir, "../x.rs").is_err());
+
+        let file = within(&workdir, "src/lib.rs").unwrap();
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, "pub fn f() {}\n").unwrap();
+        let sha = commit(
+            &workdir,
+            std::slice::from_ref(&file),
+            "generate: attempt 1/5",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(sha.len(), 40);
+        assert_eq!(
+            commit(
+                &workdir,
+                std::slice::from_ref(&file),
+                "generate: attempt 2/5"
+            )
+            .unwrap(),
+            None
+        );
+
+        // Something the user staged stays staged and out of the commit
+        std::fs::write(workdir.join("notes.txt"), "mine\n").unwrap();
+        git(&workdir, &["add", "notes.txt"]).unwrap();
+        std::fs::write(&file, "pub fn g() {}\n").unwrap();
+        commit(
+            &workdir,
+            std::slice::from_ref(&file),
+            "generate: attempt 3/5",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            git(&workdir, &["show", "--name-only", "--format=", "HEAD"]).unwrap(),
+            "src/lib.rs"
+        );
+        assert_eq!(
+            git(&workdir, &["diff", "--cached", "--name-only"]).unwrap(),
+            "notes.txt"
+        );
+
+        // A second worktree on the same branch name keeps its commits
+        git(
+            &repo,
+            &[
+                "worktree",
+                "remove",
+                "--force",
+                &workdir.display().to_string(),
+            ],
+        )
+        .unwrap();
+        let again = prepare(&options, "abc123").unwrap();
+        assert_eq!(
+            git(&again, &["rev-parse", "HEAD"]).unwrap(),
+            git(&repo, &["rev-parse", "bt/abc123"]).unwrap()
+        );
+        assert!(again.join("src/lib.rs").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+// Shared HTTP plumbing for the model backends
+
+use bt_core::BtError;
+use std::future::Future;
+
+/// Run a future to completion from the synchronous tool body.
+///
+/// `main` already runs inside tokio, so this borrows the current runtime
+/// rather than starting a nested one.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
+
+/// Map a transport failure: timeouts stay retryable, an unreachable
+/// endpoint is a missing dependency
+pub fn send_error(url: &str, e: reqwest::Error) -> BtError {
+    if e.is_timeout() {
+        BtError::Timeout(format!("Request to {} timed out", url))
+    } else {
+        BtError::DependencyMissing(format!("Could not reach {}: {}", url, e))
+    }
+}
+
+/// Map a failure while reading a body that already started: a dropped
+/// connection is transient, unlike an endpoint that was never reachable
+pub fn read_error(url: &str, e: reqwest::Error) -> BtError {
+    if e.is_timeout() {
+        BtError::Timeout(format!("Reading from {} timed out", url))
+    } else {
+        BtError::Io(format!("Failed to read response from {}: {}", url, e))
+    }
+}
+
+/// Feed each line of a streamed body to `f` as it arrives (SSE and NDJSON both fit)
+pub async fn for_each_line(
+    response: &mut reqwest::Response,
+    url: &str,
+    mut f: impl FnMut(&str) -> Result<(), BtError>,
+) -> Result<(), BtError> {
+    let mut pending = String::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| read_error(url, e))? {
+        pending.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(end) = pending.find('\n') {
+            let line: String = pending.drain(..=end).collect();
+            f(line.trim_end())?;
+        }
+    }
+    if !pending.trim().is_empty() {
+        f(pending.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Parse a JSON body, turning non-2xx statuses into errors with the server's message
+pub async fn json_body(
+    response: reqwest::Response,
+    url: &str,
+) -> Result<serde_json::Value, BtError> {
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| BtError::Io(format!("Failed to read response from {}: {}", url, e)))?;
+    if !status.is_success() {
+        let detail = serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|v| {
+                v["error"]["message"]
+                    .as_str()
+                    .or(v["error"].as_str())
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| text.chars().take(500).collect());
+        let message = format!("{} returned {}: {}", url, status, detail);
+        // Rate limits and server errors are transient, so map them to retryable codes
+        return Err(match status.as_u16() {
+            408 | 504 => BtError::Timeout(message),
+            429 | 500..=599 => BtError::Io(message),
+            400..=499 => BtError::InvalidInput(message),
+            _ => BtError::Internal(message),
+        });
+    }
+    serde_json::from_str(&text)
+        .map_err(|e| BtError::Internal(format!("Invalid JSON from {}: {}", url, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[tokio::test]
+    async fn test_dropped_stream_is_retryable() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        // One chunk of a chunked body, then the connection closes mid-stream
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nhello\n\r\n",
+            );
+        });
+
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let mut response = client.get(&url).send().await.unwrap();
+        let mut lines = vec![];
+        let result = for_each_line(&mut response, &url, |line| {
+            lines.push(line.to_string());
+            Ok(())
+        })
+        .await;
+        assert_eq!(lines, vec!["hello"]);
+        assert!(matches!(result, Err(BtError::Io(_))));
+    }
+}
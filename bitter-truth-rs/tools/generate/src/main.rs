@@ -1,10 +1,27 @@
+mod anthropic;
+mod cache;
+mod cost;
+mod edit;
+mod files;
+mod git;
+mod http;
+mod ollama;
+mod openai;
+mod opencode;
+mod progress;
+mod prompt;
+mod provider;
+mod repo_context;
+
 use anyhow::{anyhow, Result};
-use bt_core::{error_exit, log_stderr, success_exit, Context, LogEntry};
+use bt_core::usage::{self, Usage};
+use bt_core::{log_stderr, BtError, Context, LogEntry, RetryPolicy, Span, ToolConfig, ToolError};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::io::Read;
-use std::process::Command;
-use std::time::SystemTime;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
 
 #[derive(Debug, Deserialize)]
 struct GenerateInput {
@@ -15,267 +32,540 @@ struct GenerateInput {
     context: Context,
     #[serde(default = "default_feedback")]
     feedback: String,
-    #[serde(default = "default_attempt")]
-    attempt: String,
-    #[serde(default = "default_output_path")]
+    #[serde(default = "default_attempt", deserialize_with = "bt_core::retry::deserialize_attempt")]
+    attempt: u32,
+    #[serde(default)]
+    retry: RetryPolicy,
+    #[serde(default)]
     output_path: String,
-    #[serde(default = "default_model")]
+    /// When set, the response may hold several files, written as a tree here
+    #[serde(default)]
+    output_dir: String,
+    #[serde(default)]
     model: String,
+    /// One of `provider::PROVIDERS` (default `opencode`); falls back to `[generate] provider`
+    #[serde(default)]
+    provider: String,
+    /// Overrides the provider's configured `base_url`
+    #[serde(default)]
+    base_url: String,
+    /// Overrides `[generate.opencode]` binary, args and env
+    #[serde(default)]
+    opencode: Option<opencode::OpencodeOverrides>,
+    /// Template name in the templates directory, or a path to a `.j2` file
+    #[serde(default)]
+    prompt_template: String,
+    /// `edit` sends the existing `output_path` and applies the diff the model returns
+    #[serde(default)]
+    mode: edit::Mode,
+    /// Sampling parameters forwarded to the provider (temperature, max_tokens, seed, top_p)
+    #[serde(default)]
+    params: provider::GenerationParams,
+    /// Repo files to include in the prompt; falls back to `[generate.context]`
+    #[serde(default)]
+    repo_context: Option<repo_context::ContextSpec>,
+    /// Commit the output to this repo/worktree; relative output paths resolve inside it
+    #[serde(default)]
+    git: Option<git::GitOptions>,
+    /// Overrides `[generate.cache] enabled`
+    #[serde(default)]
+    cache: Option<bool>,
     #[serde(default)]
     dry_run: bool,
 }
 
+/// The `[generate]` config table
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct GenerateConfig {
+    provider: Option<String>,
+    /// Prompt templates; defaults to `templates/` next to the config file
+    templates_dir: Option<String>,
+    /// Per-model prices (USD per million tokens), matched by substring
+    pricing: HashMap<String, cost::Price>,
+    cache: cache::CacheConfig,
+    context: repo_context::ContextSpec,
+    #[serde(flatten)]
+    providers: provider::Registry,
+}
+
 fn default_feedback() -> String {
     "Initial generation".to_string()
 }
-fn default_attempt() -> String {
-    "1/5".to_string()
-}
-fn default_output_path() -> String {
-    format!("/tmp/generated_{}.rs", uuid::Uuid::new_v4())
-}
-fn default_model() -> String {
-    "anthropic/claude-opus-4-5".to_string()
+fn default_attempt() -> u32 {
+    1
 }
 
 #[derive(Debug, Serialize)]
 struct GenerateOutput {
     generated: bool,
+    /// The written file, or the output directory for multi-file generation
     output_path: String,
+    /// Files written under the output directory
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    files: Vec<files::ManifestEntry>,
     language: String,
+    mode: edit::Mode,
+    /// Repo files packed into the prompt
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    context_files: Vec<String>,
+    /// Commit holding this attempt's output, when `git` was requested and something changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+    /// Tokens and estimated cost of the model call; absent on dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<Usage>,
+    /// True when token counts were computed locally rather than reported by the provider
+    tokens_estimated: bool,
+    /// The reply came from the response cache; nothing was billed
+    cache_hit: bool,
     was_dry_run: bool,
 }
 
+/// Extracted code plus what it cost to produce
+struct Generated {
+    raw: String,
+    context_files: Vec<String>,
+    usage: Usage,
+    tokens_estimated: bool,
+    cache_hit: bool,
+}
+
 #[tokio::main]
-async fn main() {
-    let start = SystemTime::now();
-    let mut input_str = String::new();
-    if std::io::stdin().read_to_string(&mut input_str).is_err() {
-        eprintln!("Failed to read stdin");
-        std::process::exit(1);
-    }
+async fn main() -> ExitCode {
+    bt_core::run(generate)
+}
 
-    let input: GenerateInput = match serde_json::from_str(&input_str) {
-        Ok(i) => i,
-        Err(e) => {
-            let log = LogEntry::error(format!("Invalid JSON input: {}", e), "unknown".to_string());
-            log_stderr(&log);
-            error_exit(format!("Invalid JSON: {}", e), "unknown".to_string(), start);
-        }
+fn generate(mut input: GenerateInput) -> Result<GenerateOutput, ToolError> {
+    let trace_id = input.context.trace_id.clone();
+
+    let config = ToolConfig::load()?;
+    let mut section: GenerateConfig = bt_core::config::load_section("generate")?;
+    if input.provider.is_empty() {
+        input.provider = section.provider.clone().unwrap_or_else(|| "opencode".to_string());
+    }
+    if !input.base_url.is_empty() {
+        section.providers.set_base_url(&input.base_url);
+    }
+    if let Some(overrides) = &input.opencode {
+        section.providers.opencode.apply(overrides);
+    }
+    let provider = section.providers.get(&input.provider)?;
+    let templates_dir = match &section.templates_dir {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => bt_core::config::config_path()?.and_then(|p| p.parent().map(|d| d.join("templates"))),
     };
+    // Resolve the template up front so a bad name fails before any model call
+    let template = prompt::load(&input.prompt_template, templates_dir.as_deref())?;
+    if input.model.is_empty() {
+        // A provider-specific model wins over the global default
+        input.model = provider.model().unwrap_or(&config.model).to_string();
+    }
+    if input.git.is_some() && input.output_path.is_empty() && input.output_dir.is_empty() {
+        return Err(BtError::InvalidInput("output_path or output_dir is required with git".to_string()).into());
+    }
+    // A defaulted output_path lives under the tool's output_dir, not the git workdir
+    let output_path_defaulted = input.output_path.is_empty();
+    if input.output_path.is_empty() {
+        input.output_path = format!("{}/generated_{}.rs", config.output_dir, uuid::Uuid::new_v4());
+    }
 
-    let trace_id = input.context.trace_id.clone();
     let dry_run = input.dry_run || input.context.dry_run;
 
     // Validate required fields
     if input.contract_path.is_empty() {
-        let log = LogEntry::error("contract_path is required", trace_id.clone());
-        log_stderr(&log);
-        error_exit("contract_path is required".to_string(), trace_id, start);
+        return Err(BtError::InvalidInput("contract_path is required".to_string()).into());
     }
 
     if input.task.is_empty() {
-        let log = LogEntry::error("task is required", trace_id.clone());
-        log_stderr(&log);
-        error_exit("task is required".to_string(), trace_id, start);
+        return Err(BtError::InvalidInput("task is required".to_string()).into());
     }
 
     // Check contract file exists
     if !std::path::Path::new(&input.contract_path).exists() {
-        let log = LogEntry::error(
-            format!("contract not found: {}", input.contract_path),
-            trace_id.clone(),
-        );
-        log_stderr(&log);
-        error_exit(
-            format!("Contract not found: {}", input.contract_path),
-            trace_id,
-            start,
-        );
+        return Err(BtError::NotFound(format!("Contract not found: {}", input.contract_path)).into());
+    }
+
+    if input.mode == edit::Mode::Edit && !input.output_dir.is_empty() {
+        return Err(BtError::InvalidInput("mode \"edit\" works on a single output_path, not output_dir".to_string()).into());
     }
 
     let log = LogEntry::info("generating code from contract", trace_id.clone())
         .with_extra("contract", serde_json::Value::String(input.contract_path.clone()))
         .with_extra("task", serde_json::Value::String(input.task.clone()))
         .with_extra("language", serde_json::Value::String(input.language.clone()))
-        .with_extra("attempt", serde_json::Value::String(input.attempt.clone()))
+        .with_extra("provider", serde_json::Value::String(input.provider.clone()))
+        .with_extra("mode", serde_json::json!(input.mode))
+        .with_extra("attempt", serde_json::Value::String(input.retry.attempt_label(input.attempt)))
         .with_extra("dry_run", serde_json::Value::Bool(dry_run));
     log_stderr(&log);
 
+    if dry_run && input.mode == edit::Mode::Edit {
+        // Dry-run in edit mode: leave the existing file alone
+        return Ok(GenerateOutput {
+            generated: false,
+            output_path: input.output_path.clone(),
+            files: vec![],
+            context_files: vec![],
+            commit: None,
+            language: input.language.clone(),
+            mode: input.mode,
+            usage: None,
+            tokens_estimated: false,
+            cache_hit: false,
+            was_dry_run: true,
+        });
+    }
+
     if dry_run {
         // Dry-run: create a stub file
         let stub = format!("// Dry-run stub for {}\nfn main() {{\n    println!(\"dry-run\");\n}}\n", input.language);
-        if let Err(e) = fs::write(&input.output_path, &stub) {
-            let log = LogEntry::error(format!("Failed to write stub: {}", e), trace_id.clone());
-            log_stderr(&log);
-            error_exit(
-                format!("Failed to write stub: {}", e),
-                trace_id,
-                start,
-            );
+        if !input.output_dir.is_empty() {
+            fs::create_dir_all(&input.output_dir)
+                .map_err(|e| BtError::Io(format!("Failed to create {}: {}", input.output_dir, e)))?;
         }
+        let stub_path = single_file_path(&input.output_path, &input.output_dir);
+        fs::write(&stub_path, &stub)
+            .map_err(|e| BtError::Io(format!("Failed to write stub: {}", e)))?;
 
-        let output = GenerateOutput {
+        return Ok(GenerateOutput {
             generated: true,
-            output_path: input.output_path.clone(),
+            output_path: stub_path.display().to_string(),
+            files: vec![],
+            context_files: vec![],
+            commit: None,
             language: input.language.clone(),
+            mode: input.mode,
+            usage: None,
+            tokens_estimated: false,
+            cache_hit: false,
             was_dry_run: true,
-        };
-
-        success_exit(output, trace_id.clone(), start);
+        });
     }
 
-    // Real generation: call opencode
-    match generate_code(&input, &trace_id.clone()) {
-        Ok(code) => {
-            if let Err(e) = fs::write(&input.output_path, &code) {
-                let log = LogEntry::error(format!("Failed to write generated code: {}", e), trace_id.clone());
-                log_stderr(&log);
-                error_exit(
-                    format!("Failed to write code: {}", e),
-                    trace_id,
-                    start,
-                );
+    // Only a real run touches git: a dry run must not create worktrees or branches
+    let git_workdir = match &input.git {
+        Some(options) => {
+            let workdir = git::prepare(options, &trace_id)?;
+            if !output_path_defaulted {
+                input.output_path = git::within(&workdir, &input.output_path)?.display().to_string();
             }
+            if !input.output_dir.is_empty() {
+                input.output_dir = git::within(&workdir, &input.output_dir)?.display().to_string();
+            }
+            Some(workdir)
+        }
+        None => None,
+    };
+    if !input.output_dir.is_empty() {
+        fs::create_dir_all(&input.output_dir)
+            .map_err(|e| BtError::Io(format!("Failed to create {}: {}", input.output_dir, e)))?;
+    }
+    let existing = match input.mode {
+        edit::Mode::Full => String::new(),
+        edit::Mode::Edit => fs::read_to_string(&input.output_path)
+            .map_err(|e| BtError::NotFound(format!("Edit mode needs an existing file at {}: {}", input.output_path, e)))?,
+    };
 
-            let log = LogEntry::info("code generation successful", trace_id.clone())
-                .with_extra("output_path", serde_json::Value::String(input.output_path.clone()))
-                .with_extra("code_length", serde_json::Value::Number(code.len().into()));
-            log_stderr(&log);
+    // Real generation: call the model provider
+    provider.check()?;
+    let price = cost::price_for(&input.provider, &input.model, &section.pricing);
+    let cache = input
+        .cache
+        .unwrap_or(section.cache.enabled)
+        .then(|| cache::Cache::new(&section.cache, &config.output_dir));
+    let context_spec = input.repo_context.clone().unwrap_or_else(|| section.context.clone());
+    let Generated {
+        raw,
+        context_files,
+        usage,
+        tokens_estimated,
+        cache_hit,
+    } = generate_code(&input, &template, &existing, &context_spec, provider, price, cache.as_ref())?;
+
+    let tree = if input.output_dir.is_empty() { vec![] } else { files::parse(&raw) };
+    let (output_path, manifest) = if tree.is_empty() {
+        let code = {
+            let _span = Span::enter("extract", &trace_id);
+            match input.mode {
+                edit::Mode::Edit => apply_edit(&existing, &raw, &trace_id)?,
+                // Extract code using llm-cleaner
+                edit::Mode::Full => extract_code(&raw, &input.language, &trace_id)?,
+            }
+        };
+        let _span = Span::enter("write", &trace_id);
+        let path = single_file_path(&input.output_path, &input.output_dir);
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| BtError::Io(format!("Failed to create {}: {}", parent.display(), e)))?;
+        }
+        fs::write(&path, &code)
+            .map_err(|e| BtError::Io(format!("Failed to write code: {}", e)))?;
+        (path.display().to_string(), vec![])
+    } else {
+        let _span = Span::enter("write", &trace_id);
+        (input.output_dir.clone(), files::write_tree(Path::new(&input.output_dir), &tree)?)
+    };
 
-            let output = GenerateOutput {
-                generated: true,
-                output_path: input.output_path.clone(),
-                language: input.language.clone(),
-                was_dry_run: false,
+    let commit = match &git_workdir {
+        Some(workdir) => {
+            let _span = Span::enter("git", &trace_id);
+            let written: Vec<PathBuf> = if manifest.is_empty() {
+                vec![PathBuf::from(&output_path)]
+            } else {
+                manifest.iter().map(|f| Path::new(&output_path).join(&f.path)).collect()
             };
-
-            success_exit(output, trace_id, start);
-        }
-        Err(e) => {
-            let log = LogEntry::error(format!("Code generation failed: {}", e), trace_id.clone());
-            log_stderr(&log);
-            error_exit(
-                format!("Generation failed: {}", e),
+            let message = format!(
+                "generate: {}\n\nTrace-Id: {}\nAttempt: {}\nModel: {}",
+                input.task.lines().next().unwrap_or_default(),
                 trace_id,
-                start,
+                input.retry.attempt_label(input.attempt),
+                input.model
             );
+            git::commit(workdir, &written, &message)?
         }
-    }
-}
+        None => None,
+    };
 
-fn generate_code(input: &GenerateInput, trace_id: &str) -> Result<String> {
-    // Validate opencode is available
-    let models_output = Command::new("opencode")
-        .arg("models")
-        .output()?;
+    let log = LogEntry::info("code generation successful", trace_id.clone())
+        .with_extra("output_path", serde_json::Value::String(output_path.clone()))
+        .with_extra("files", serde_json::json!(manifest.len().max(1)))
+        .with_extra("commit", serde_json::json!(commit));
+    log_stderr(&log);
 
-    if !models_output.status.success() {
-        return Err(anyhow!("Failed to list opencode models"));
+    Ok(GenerateOutput {
+        generated: true,
+        output_path,
+        files: manifest,
+        context_files,
+        commit,
+        language: input.language.clone(),
+        mode: input.mode,
+        usage: Some(usage),
+        tokens_estimated,
+        cache_hit,
+        was_dry_run: false,
+    })
+}
+
+/// Where a single-file result goes: named after `output_path`, but inside
+/// `output_dir` when one was requested and the reply held no file tree
+fn single_file_path(output_path: &str, output_dir: &str) -> PathBuf {
+    match Path::new(output_path).file_name() {
+        Some(name) if !output_dir.is_empty() => Path::new(output_dir).join(name),
+        _ => PathBuf::from(output_path),
     }
+}
 
-    let models_str = String::from_utf8(models_output.stdout)?;
-    let available_models: Vec<&str> = models_str.lines().collect();
+/// Deletes a scratch file when dropped
+struct RemoveOnDrop<'a>(&'a Path);
 
-    // Check if model is available
-    if !available_models.iter().any(|m| m.contains(&input.model)) {
-        return Err(anyhow!(
-            "Model '{}' not available. Available: {}",
-            input.model,
-            available_models.join(", ")
-        ));
+impl Drop for RemoveOnDrop<'_> {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(self.0);
     }
+}
 
+fn generate_code(
+    input: &GenerateInput,
+    template: &str,
+    existing: &str,
+    context_spec: &repo_context::ContextSpec,
+    provider: &dyn provider::Provider,
+    price: Option<cost::Price>,
+    cache: Option<&cache::Cache>,
+) -> Result<Generated> {
+    let trace_id = input.context.trace_id.as_str();
     // Read contract
     let contract_content = fs::read_to_string(&input.contract_path)?;
 
+    let packed = repo_context::pack(context_spec, &input.task, &input.language)?;
+    if !packed.files.is_empty() {
+        let log = LogEntry::info("packed repository context", trace_id.to_string())
+            .with_extra("files", serde_json::json!(packed.files))
+            .with_extra("bytes", serde_json::json!(packed.text.len()));
+        log_stderr(&log);
+    }
+
     // Build prompt
-    let prompt = build_prompt(input, &contract_content);
+    let prompt = prompt::render(
+        template,
+        &prompt::PromptVars {
+            language: &input.language,
+            task: &input.task,
+            contract: &contract_content,
+            feedback: &input.feedback,
+            attempt: input.retry.attempt_label(input.attempt),
+            multi_file: !input.output_dir.is_empty(),
+            repo_context: &packed.text,
+            edit: input.mode == edit::Mode::Edit,
+            existing,
+        },
+    )?;
+
+    // Streaming providers mirror the reply here so watchers can follow along
+    let partial_path = std::path::PathBuf::from(format!("{}.partial", input.output_path));
+    let params = provider::Params {
+        model: &input.model,
+        timeout: Duration::from_secs(input.context.timeout_seconds.unwrap_or(300)),
+        trace_id,
+        partial_path: Some(&partial_path),
+        sampling: input.params.or(provider.defaults()),
+    };
+    let key = cache::Cache::key(&input.provider, &input.model, &params.sampling, &prompt);
+    if let Some(hit) = cache.and_then(|c| c.get(&key)) {
+        let log = LogEntry::info("response cache hit", trace_id.to_string())
+            .with_extra("key", serde_json::Value::String(key));
+        log_stderr(&log);
+        return Ok(Generated {
+            raw: hit.text,
+            context_files: packed.files,
+            usage: Usage::default(),
+            tokens_estimated: false,
+            cache_hit: true,
+        });
+    }
 
-    let log = LogEntry::info("calling opencode", trace_id.to_string())
-        .with_extra("model", serde_json::Value::String(input.model.clone()))
-        .with_extra("prompt_length", serde_json::Value::Number(prompt.len().into()));
-    log_stderr(&log);
+    // Removed however the call ends, including a stream that fails halfway
+    let _partial = RemoveOnDrop(&partial_path);
+    let generation = provider::call(provider, &prompt, &params)?;
 
-    // Call opencode
-    let output = Command::new("opencode")
-        .arg("run")
-        .arg("-m")
-        .arg(&input.model)
-        .arg(&prompt)
-        .output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow!("opencode failed: {}", stderr));
+    let (usage, tokens_estimated) = cost::complete(generation.usage, &prompt, &generation.text, &input.model, price);
+    usage::record(usage);
+    if let Some(cache) = cache.filter(|_| !generation.text.trim().is_empty()) {
+        cache.put(&key, &provider::Generation { text: generation.text.clone(), usage });
     }
+    let log = LogEntry::info("model usage", trace_id.to_string())
+        .with_extra("model", serde_json::Value::String(input.model.clone()))
+        .with_extra("prompt_tokens", serde_json::json!(usage.prompt_tokens))
+        .with_extra("completion_tokens", serde_json::json!(usage.completion_tokens))
+        .with_extra("cost_usd", serde_json::json!(usage.cost_usd))
+        .with_extra("tokens_estimated", serde_json::Value::Bool(tokens_estimated));
+    log_stderr(&log);
 
-    let raw_output = String::from_utf8(output.stdout)?;
-
-    if raw_output.trim().is_empty() {
-        return Err(anyhow!("Empty response from opencode"));
+    if generation.text.trim().is_empty() {
+        return Err(anyhow!("Empty response from {}", input.provider));
     }
 
-    // Extract code using llm-cleaner
-    let code = extract_code(&raw_output, &input.language, trace_id)?;
-    Ok(code)
+    Ok(Generated {
+        raw: generation.text,
+        context_files: packed.files,
+        usage,
+        tokens_estimated,
+        cache_hit: false,
+    })
 }
 
-fn extract_code(output: &str, language: &str, trace_id: &str) -> Result<String> {
-    // Try to find llm-cleaner binary
-    let llm_cleaner_paths = [
-        "/home/lewis/src/Fire-Flow/tools/llm-cleaner/target/release/llm-cleaner",
-        "./tools/llm-cleaner/target/release/llm-cleaner",
-        "/usr/local/bin/llm-cleaner",
-        "llm-cleaner",
-    ];
-
-    let llm_cleaner = llm_cleaner_paths
-        .iter()
-        .find(|p| std::path::Path::new(p).exists())
-        .copied()
-        .ok_or_else(|| anyhow!("llm-cleaner not found"))?;
-
-    let log = LogEntry::info("extracting code with llm-cleaner", trace_id.to_string())
-        .with_extra("cleaner_path", serde_json::Value::String(llm_cleaner.to_string()));
+fn extract_code(output: &str, language: &str, trace_id: &str) -> Result<String, BtError> {
+    let log = LogEntry::debug("extracting code", trace_id.to_string())
+        .with_extra("input_length", serde_json::json!(output.len()));
     log_stderr(&log);
 
-    let output_result = Command::new(llm_cleaner)
-        .arg("--lang")
-        .arg(language)
-        .arg("--debug")
-        .output()?;
+    let extraction = llm_cleaner::extract_code_block(output, Some(language)).map_err(|e| extraction_error(e, language))?;
+    let log = LogEntry::debug("extracted code", trace_id.to_string())
+        .with_extra("source", serde_json::json!(extraction.source.describe()))
+        .with_extra("length", serde_json::json!(extraction.content.len()));
+    log_stderr(&log);
+    let code = extraction.content;
+    if code.trim().is_empty() {
+        return Err(BtError::ContractViolation(format!("Extracted no {} code", language)));
+    }
+    Ok(code)
+}
 
-    if output_result.status.success() {
-        let code = String::from_utf8(output_result.stdout)?;
-        Ok(code)
-    } else {
-        // Fallback: use raw output
-        let log = LogEntry::error("llm-cleaner failed, using raw output", trace_id.to_string());
-        log_stderr(&log);
-        Ok(output.to_string())
+fn extraction_error(error: llm_cleaner::ExtractError, language: &str) -> BtError {
+    use llm_cleaner::ExtractError;
+    match error {
+        // No usable code in the reply is the model's fault; a fresh attempt may do better
+        ExtractError::EmptyBlock | ExtractError::NoCode { .. } => BtError::ContractViolation(format!("Could not extract {} code: {}", language, error)),
+        // Only block selection and JSON extraction produce these, and generate asks for neither
+        ExtractError::NoSuchBlock { .. } | ExtractError::NoJson => BtError::Internal(format!("Unexpected extraction error: {}", error)),
     }
 }
 
-fn build_prompt(input: &GenerateInput, contract: &str) -> String {
-    format!(
-        r#"You are a {} code generator. Output ONLY valid {} code, never explanations.
+fn apply_edit(existing: &str, output: &str, trace_id: &str) -> Result<String, BtError> {
+    let diff = edit::extract_diff(output)?;
+    let (code, hunks) = edit::apply(existing, &diff)?;
+    let log = LogEntry::info("applied diff", trace_id.to_string())
+        .with_extra("hunks", serde_json::json!(hunks))
+        .with_extra("diff_bytes", serde_json::json!(diff.len()))
+        .with_extra("file_bytes", serde_json::json!(code.len()));
+    log_stderr(&log);
+    Ok(code)
+}
 
-TASK: {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extraction_errors_map_to_codes() {
+        use llm_cleaner::ExtractError;
+        let cases = [
+            (ExtractError::EmptyBlock, bt_core::ErrorCode::ContractViolation, true),
+            (ExtractError::NoCode { preview: "Sorry".to_string() }, bt_core::ErrorCode::ContractViolation, true),
+            (ExtractError::NoSuchBlock { index: 2, count: 1 }, bt_core::ErrorCode::Internal, false),
+            (ExtractError::NoJson, bt_core::ErrorCode::Internal, false),
+        ];
+        for (error, code, retryable) in cases {
+            let tool_error = ToolError::from(extraction_error(error.clone(), "rust"));
+            assert_eq!((tool_error.code, tool_error.retryable), (code, retryable), "{:?}", error);
+        }
+
+        let empty = extract_code("```rust\n\n```", "rust", "t").unwrap_err();
+        assert_eq!(empty.code(), bt_core::ErrorCode::ContractViolation);
+        assert_eq!(extract_code("```rust\nfn main() {}\n```", "rust", "t").unwrap(), "fn main() {}");
+    }
 
-CONTRACT (your output must produce data matching this schema):
-{}
+    #[test]
+    fn test_single_file_result_stays_in_output_dir() {
+        assert_eq!(single_file_path("/tmp/generated_ab12.rs", "out/pkg"), PathBuf::from("out/pkg/generated_ab12.rs"));
+        assert_eq!(single_file_path("src/lib.rs", ""), PathBuf::from("src/lib.rs"));
+    }
 
-FEEDBACK FROM PREVIOUS ATTEMPT: {}
-ATTEMPT: {}
+    /// Streams part of a reply to the partial file, then fails
+    struct Broken {
+        settings: provider::CallSettings,
+        defaults: provider::GenerationParams,
+    }
 
-REQUIREMENTS:
-- Output must match the contract schema exactly
-- Return success/error appropriately
-- Output valid, runnable code
+    impl provider::Provider for Broken {
+        fn name(&self) -> &'static str {
+            "broken"
+        }
+        fn model(&self) -> Option<&str> {
+            None
+        }
+        fn settings(&self) -> &provider::CallSettings {
+            &self.settings
+        }
+        fn defaults(&self) -> &provider::GenerationParams {
+            &self.defaults
+        }
+        fn generate(&self, _prompt: &str, params: &provider::Params) -> Result<provider::Generation, BtError> {
+            fs::write(params.partial_path.unwrap(), "fn main() {").unwrap();
+            Err(BtError::InvalidInput("stream closed".to_string()))
+        }
+    }
 
-Generate the complete {} code for the task.
-OUTPUT ONLY THE CODE:"#,
-        input.language, input.language, input.task, contract, input.feedback, input.attempt, input.language
-    )
+    #[test]
+    fn test_failed_generation_removes_partial_file() {
+        let dir = std::env::temp_dir().join(format!("generate-partial-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let contract = dir.join("contract.yaml");
+        fs::write(&contract, "name: x\n").unwrap();
+        let output = dir.join("out.rs");
+        let input: GenerateInput = serde_json::from_value(serde_json::json!({
+            "contract_path": contract,
+            "task": "write main",
+            "language": "rust",
+            "output_path": output,
+        }))
+        .unwrap();
+
+        let provider = Broken { settings: provider::CallSettings::default(), defaults: provider::GenerationParams::default() };
+        let result = generate_code(&input, "{{ task }}", "", &repo_context::ContextSpec::default(), &provider, None, None);
+        assert!(result.is_err());
+        assert!(!dir.join("out.rs.partial").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,223 @@
+// Ollama backend for fully local generation
+
+use crate::progress::Progress;
+use crate::provider::{CallSettings, Generation, GenerationParams, Params, Provider};
+use bt_core::usage::Usage;
+use bt_core::{log_stderr, BtError, LogEntry};
+use serde::Deserialize;
+
+/// `[generate.ollama]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OllamaConfig {
+    /// Server root; `/api/chat` is appended
+    pub base_url: String,
+    /// Overrides the global `model` for this provider
+    pub model: Option<String>,
+    /// Sampling defaults; `max_tokens` maps to `num_predict`
+    #[serde(flatten)]
+    pub defaults: GenerationParams,
+    /// Context window in tokens (`num_ctx`)
+    pub num_ctx: Option<u32>,
+    /// Stream the reply as NDJSON, logging progress as it arrives
+    pub stream: bool,
+    #[serde(flatten)]
+    pub call: CallSettings,
+}
+
+impl Default for OllamaConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:11434".to_string(),
+            model: None,
+            defaults: GenerationParams::default(),
+            num_ctx: None,
+            stream: true,
+            call: CallSettings::default(),
+        }
+    }
+}
+
+impl OllamaConfig {
+    /// The `/api/chat` request: one user turn, with sampling mapped to Ollama's option names
+    fn body(&self, prompt: &str, params: &Params) -> serde_json::Value {
+        let mut options = serde_json::Map::new();
+        let sampling = &params.sampling;
+        if let Some(temperature) = sampling.temperature {
+            options.insert("temperature".to_string(), temperature.into());
+        }
+        if let Some(max_tokens) = sampling.max_tokens {
+            options.insert("num_predict".to_string(), max_tokens.into());
+        }
+        if let Some(seed) = sampling.seed {
+            options.insert("seed".to_string(), seed.into());
+        }
+        if let Some(top_p) = sampling.top_p {
+            options.insert("top_p".to_string(), top_p.into());
+        }
+        if let Some(num_ctx) = self.num_ctx {
+            options.insert("num_ctx".to_string(), num_ctx.into());
+        }
+        serde_json::json!({
+            "model": params.model,
+            "messages": [{"role": "user", "content": prompt}],
+            "stream": self.stream,
+            "options": options,
+        })
+    }
+}
+
+impl Provider for OllamaConfig {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    fn settings(&self) -> &CallSettings {
+        &self.call
+    }
+
+    fn defaults(&self) -> &GenerationParams {
+        &self.defaults
+    }
+
+    /// Send `prompt` as a single chat turn and return the reply text
+    fn generate(&self, prompt: &str, params: &Params) -> Result<Generation, BtError> {
+        let url = format!("{}/api/chat", self.base_url.trim_end_matches('/'));
+        let body = self.body(prompt, params);
+
+        let log = LogEntry::info("calling ollama", params.trace_id.to_string())
+            .with_extra("url", serde_json::Value::String(url.clone()))
+            .with_extra("model", serde_json::Value::String(params.model.to_string()));
+        log_stderr(&log);
+
+        crate::http::block_on(async {
+            let client = reqwest::Client::builder()
+                .timeout(params.timeout)
+                .build()
+                .map_err(|e| BtError::Internal(format!("HTTP client: {}", e)))?;
+            let mut response = client
+                .post(&url)
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| crate::http::send_error(&url, e))?;
+
+            if !self.stream || !response.status().is_success() {
+                let response = crate::http::json_body(response, &url).await?;
+                let text = response["message"]["content"]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| {
+                        BtError::Internal(format!("No message content in response from {}", url))
+                    })?;
+                return Ok(Generation {
+                    text,
+                    usage: usage_from(&response),
+                });
+            }
+
+            let mut generation = Generation::default();
+            let mut progress = Progress::new("ollama", params);
+            crate::http::for_each_line(&mut response, &url, |line| {
+                let before = generation.text.len();
+                apply_line(&mut generation, line)?;
+                progress.push(&generation.text[before..]);
+                Ok(())
+            })
+            .await?;
+            progress.finish();
+            Ok(generation)
+        })
+    }
+}
+
+/// Fold one NDJSON stream line into the reply
+fn apply_line(generation: &mut Generation, line: &str) -> Result<(), BtError> {
+    let Ok(chunk) = serde_json::from_str::<serde_json::Value>(line) else {
+        return Ok(());
+    };
+    if let Some(error) = chunk["error"].as_str() {
+        return Err(BtError::Io(format!("ollama stream error: {}", error)));
+    }
+    if let Some(text) = chunk["message"]["content"].as_str() {
+        generation.text.push_str(text);
+    }
+    if chunk["done"].as_bool() == Some(true) {
+        generation.usage = usage_from(&chunk);
+    }
+    Ok(())
+}
+
+/// Token counts from a final (`done`) response
+fn usage_from(response: &serde_json::Value) -> Usage {
+    Usage {
+        prompt_tokens: response["prompt_eval_count"].as_u64().unwrap_or(0),
+        completion_tokens: response["eval_count"].as_u64().unwrap_or(0),
+        cost_usd: 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_request_body_maps_sampling() {
+        let config = OllamaConfig {
+            num_ctx: Some(8192),
+            ..OllamaConfig::default()
+        };
+        let params = Params {
+            model: "qwen2.5-coder",
+            timeout: Duration::from_secs(5),
+            trace_id: "t",
+            partial_path: None,
+            sampling: GenerationParams {
+                temperature: Some(0.2),
+                max_tokens: Some(512),
+                ..GenerationParams::default()
+            },
+        };
+        let body = config.body("write it", &params);
+        assert_eq!(body["model"], "qwen2.5-coder");
+        assert_eq!(body["stream"], true);
+        assert_eq!(
+            body["messages"],
+            serde_json::json!([{"role": "user", "content": "write it"}])
+        );
+        assert_eq!(body["options"]["num_predict"], 512);
+        assert_eq!(body["options"]["num_ctx"], 8192);
+        assert!((body["options"]["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+        assert!(body["options"].get("seed").is_none());
+    }
+
+    #[test]
+    fn test_stream_lines_accumulate_reply() {
+        let lines = [
+            r#"{"model":"qwen","message":{"role":"assistant","content":"fn main() "},"done":false}"#,
+            "",
+            r#"{"model":"qwen","message":{"role":"assistant","content":"{}"},"done":false}"#,
+            r#"{"model":"qwen","message":{"role":"assistant","content":""},"done":true,"prompt_eval_count":12,"eval_count":7}"#,
+        ];
+        let mut generation = Generation::default();
+        for line in lines {
+            apply_line(&mut generation, line).unwrap();
+        }
+        assert_eq!(generation.text, "fn main() {}");
+        assert_eq!(
+            (
+                generation.usage.prompt_tokens,
+                generation.usage.completion_tokens
+            ),
+            (12, 7)
+        );
+
+        let err = apply_line(&mut generation, r#"{"error":"model 'qwen' not found"}"#);
+        assert!(matches!(err, Err(BtError::Io(_))));
+    }
+}
@@ -0,0 +1,175 @@
+// OpenAI-compatible chat completions backend (OpenAI, vLLM, LM Studio, OpenRouter, ...)
+
+use crate::progress::Progress;
+use crate::provider::{CallSettings, Generation, GenerationParams, Params, Provider};
+use bt_core::usage::Usage;
+use bt_core::{log_stderr, BtError, LogEntry};
+use serde::Deserialize;
+
+/// `[generate.openai]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OpenAiConfig {
+    /// Endpoint root; `/chat/completions` is appended
+    pub base_url: String,
+    /// Environment variable holding the API key; unset is fine for local servers
+    pub api_key_env: String,
+    /// Overrides the global `model` for this provider
+    pub model: Option<String>,
+    /// Sampling defaults (`temperature`, `max_tokens`, `seed`, `top_p`)
+    #[serde(flatten)]
+    pub defaults: GenerationParams,
+    /// Stream the reply over SSE; off by default since not every compatible server supports it
+    pub stream: bool,
+    #[serde(flatten)]
+    pub call: CallSettings,
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key_env: "OPENAI_API_KEY".to_string(),
+            model: None,
+            defaults: GenerationParams::default(),
+            stream: false,
+            call: CallSettings::default(),
+        }
+    }
+}
+
+impl Provider for OpenAiConfig {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    fn settings(&self) -> &CallSettings {
+        &self.call
+    }
+
+    fn defaults(&self) -> &GenerationParams {
+        &self.defaults
+    }
+
+    /// Send `prompt` as a single user message and return the reply text
+    fn generate(&self, prompt: &str, params: &Params) -> Result<Generation, BtError> {
+        let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
+
+        let mut body = serde_json::json!({
+            "model": params.model,
+            "messages": [{"role": "user", "content": prompt}],
+        });
+        let sampling = &params.sampling;
+        if let Some(max_tokens) = sampling.max_tokens {
+            body["max_tokens"] = max_tokens.into();
+        }
+        if let Some(temperature) = sampling.temperature {
+            body["temperature"] = temperature.into();
+        }
+        if let Some(seed) = sampling.seed {
+            body["seed"] = seed.into();
+        }
+        if let Some(top_p) = sampling.top_p {
+            body["top_p"] = top_p.into();
+        }
+        if self.stream {
+            body["stream"] = true.into();
+            body["stream_options"] = serde_json::json!({"include_usage": true});
+        }
+
+        let log = LogEntry::info(
+            "calling OpenAI-compatible endpoint",
+            params.trace_id.to_string(),
+        )
+        .with_extra("url", serde_json::Value::String(url.clone()))
+        .with_extra("model", serde_json::Value::String(params.model.to_string()));
+        log_stderr(&log);
+
+        let api_key = std::env::var(&self.api_key_env)
+            .ok()
+            .filter(|k| !k.is_empty());
+        crate::http::block_on(async {
+            let client = reqwest::Client::builder()
+                .timeout(params.timeout)
+                .build()
+                .map_err(|e| BtError::Internal(format!("HTTP client: {}", e)))?;
+            let mut request = client.post(&url).json(&body);
+            if let Some(key) = &api_key {
+                request = request.bearer_auth(key);
+            }
+            let mut response = request
+                .send()
+                .await
+                .map_err(|e| crate::http::send_error(&url, e))?;
+
+            if !self.stream || !response.status().is_success() {
+                let response = crate::http::json_body(response, &url).await?;
+                let text = response["choices"][0]["message"]["content"]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| {
+                        BtError::Internal(format!("No message content in response from {}", url))
+                    })?;
+                return Ok(Generation {
+                    text,
+                    usage: usage_from(&response["usage"]),
+                });
+            }
+
+            let mut generation = Generation::default();
+            let mut progress = Progress::new("openai", params);
+            crate::http::for_each_line(&mut response, &url, |line| {
+                let Some(data) = line.strip_prefix("data:").map(str::trim_start) else {
+                    return Ok(());
+                };
+                let Ok(chunk) = serde_json::from_str::<serde_json::Value>(data) else {
+                    return Ok(()); // includes the closing [DONE]
+                };
+                if let Some(text) = chunk["choices"][0]["delta"]["content"].as_str() {
+                    generation.text.push_str(text);
+                    progress.push(text);
+                }
+                if chunk["usage"].is_object() {
+                    generation.usage = usage_from(&chunk["usage"]);
+                }
+                Ok(())
+            })
+            .await?;
+            progress.finish();
+            Ok(generation)
+        })
+    }
+}
+
+fn usage_from(usage: &serde_json::Value) -> Usage {
+    Usage {
+        prompt_tokens: usage["prompt_tokens"].as_u64().unwrap_or(0),
+        completion_tokens: usage["completion_tokens"].as_u64().unwrap_or(0),
+        cost_usd: 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_section_overrides_defaults() {
+        let toml = "[generate.openai]\nbase_url = \"http://localhost:8000/v1\"\nmodel = \"qwen\"\n";
+        #[derive(Default, Deserialize)]
+        struct Section {
+            #[serde(default)]
+            openai: OpenAiConfig,
+        }
+        let config = bt_core::config::section_from_toml_str::<Section>(toml, "generate")
+            .unwrap()
+            .openai;
+        assert_eq!(config.base_url, "http://localhost:8000/v1");
+        assert_eq!(config.model.as_deref(), Some("qwen"));
+        assert_eq!(config.api_key_env, "OPENAI_API_KEY");
+    }
+}
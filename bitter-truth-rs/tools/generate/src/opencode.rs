@@ -0,0 +1,268 @@
+// opencode CLI backend: shells out to `opencode run`
+
+use crate::provider::{CallSettings, Generation, GenerationParams, Params, Provider};
+use bt_core::{log_stderr, BtError, LogEntry};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::Duration;
+
+/// Listing models is quick; don't let a wedged CLI eat the whole generation budget
+const MODELS_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `[generate.opencode]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OpencodeConfig {
+    /// Binary name looked up on PATH, or a path to it
+    pub binary: String,
+    /// Extra flags passed to `opencode run` before the prompt
+    pub args: Vec<String>,
+    /// Extra environment for the child; a `${NAME}` value is read from this
+    /// process's environment so keys need not be written into config files
+    pub env: HashMap<String, String>,
+    #[serde(flatten)]
+    pub call: CallSettings,
+    /// Always empty: `opencode run` takes no sampling flags
+    #[serde(skip)]
+    pub defaults: GenerationParams,
+}
+
+impl Default for OpencodeConfig {
+    fn default() -> Self {
+        Self {
+            binary: "opencode".to_string(),
+            args: vec![],
+            env: HashMap::new(),
+            call: CallSettings::default(),
+            defaults: GenerationParams::default(),
+        }
+    }
+}
+
+/// Per-request overrides for `[generate.opencode]`, from `GenerateInput.opencode`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct OpencodeOverrides {
+    pub binary: Option<String>,
+    /// Replaces the configured flags
+    pub args: Option<Vec<String>>,
+    /// Merged over the configured environment
+    pub env: HashMap<String, String>,
+}
+
+impl OpencodeConfig {
+    pub fn apply(&mut self, overrides: &OpencodeOverrides) {
+        if let Some(binary) = &overrides.binary {
+            self.binary = binary.clone();
+        }
+        if let Some(args) = &overrides.args {
+            self.args = args.clone();
+        }
+        self.env.extend(overrides.env.clone());
+    }
+
+    /// The executable `binary` names: a path as given, otherwise the first match on PATH
+    pub fn resolve_binary(&self) -> Result<PathBuf, BtError> {
+        let candidates: Vec<PathBuf> = if self.binary.contains('/') {
+            vec![PathBuf::from(&self.binary)]
+        } else {
+            std::env::var_os("PATH")
+                .map(|path| {
+                    std::env::split_paths(&path)
+                        .map(|dir| dir.join(&self.binary))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        candidates.into_iter().find(|p| is_executable(p)).ok_or_else(|| {
+            BtError::DependencyMissing(format!(
+                "opencode binary '{}' not found or not executable; install it or set [generate.opencode] binary",
+                self.binary
+            ))
+        })
+    }
+
+    /// A command for the resolved binary with the configured environment applied
+    fn command(&self, binary: &Path) -> Result<Command, BtError> {
+        let mut command = Command::new(binary);
+        for (name, value) in &self.env {
+            let value = match value.strip_prefix("${").and_then(|v| v.strip_suffix('}')) {
+                Some(var) => std::env::var(var).map_err(|_| {
+                    BtError::DependencyMissing(format!(
+                        "Environment variable {} (for opencode {}) is not set",
+                        var, name
+                    ))
+                })?,
+                None => value.clone(),
+            };
+            command.env(name, value);
+        }
+        Ok(command)
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+impl Provider for OpencodeConfig {
+    fn name(&self) -> &'static str {
+        "opencode"
+    }
+
+    /// opencode already speaks the global `provider/model` form
+    fn model(&self) -> Option<&str> {
+        None
+    }
+
+    fn settings(&self) -> &CallSettings {
+        &self.call
+    }
+
+    fn defaults(&self) -> &GenerationParams {
+        &self.defaults
+    }
+
+    fn check(&self) -> Result<(), BtError> {
+        self.resolve_binary().map(|_| ())
+    }
+
+    fn generate(&self, prompt: &str, params: &Params) -> Result<Generation, BtError> {
+        // Validate opencode is available
+        let binary = self.resolve_binary()?;
+        let models_output = run_with_timeout(
+            self.command(&binary)?.arg("models"),
+            MODELS_TIMEOUT.min(params.timeout),
+        )?;
+
+        if !models_output.status.success() {
+            return Err(BtError::DependencyMissing(
+                "Failed to list opencode models".to_string(),
+            ));
+        }
+
+        let models_str = String::from_utf8_lossy(&models_output.stdout);
+        let available_models: Vec<&str> = models_str.lines().collect();
+
+        // Check if model is available
+        if !available_models.iter().any(|m| m.contains(params.model)) {
+            return Err(BtError::DependencyMissing(format!(
+                "Model '{}' not available. Available: {}",
+                params.model,
+                available_models.join(", ")
+            )));
+        }
+
+        if params.sampling != GenerationParams::default() {
+            let log = LogEntry::warn(
+                "opencode ignores sampling params",
+                params.trace_id.to_string(),
+            )
+            .with_extra("params", serde_json::json!(params.sampling));
+            log_stderr(&log);
+        }
+
+        // Only variable names are logged; values may be credentials
+        let env: Vec<&String> = self.env.keys().collect();
+        let log = LogEntry::info("calling opencode", params.trace_id.to_string())
+            .with_extra(
+                "binary",
+                serde_json::Value::String(binary.display().to_string()),
+            )
+            .with_extra("args", serde_json::json!(self.args))
+            .with_extra("env", serde_json::json!(env))
+            .with_extra("model", serde_json::Value::String(params.model.to_string()))
+            .with_extra(
+                "prompt_length",
+                serde_json::Value::Number(prompt.len().into()),
+            );
+        log_stderr(&log);
+
+        let output = run_with_timeout(
+            self.command(&binary)?
+                .arg("run")
+                .arg("-m")
+                .arg(params.model)
+                .args(&self.args)
+                .arg(prompt),
+            params.timeout,
+        )?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(BtError::Internal(format!("opencode failed: {}", stderr)));
+        }
+
+        Ok(Generation {
+            text: String::from_utf8_lossy(&output.stdout).into_owned(),
+            ..Generation::default()
+        })
+    }
+}
+
+/// Run an opencode command, killing its whole process group if it outlives `timeout`.
+/// opencode starts helper processes of its own, so killing just the child leaves them running.
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> Result<Output, BtError> {
+    bt_core::process::output_with_timeout(command, Some(timeout))
+        .map_err(|e| BtError::DependencyMissing(format!("opencode not available: {}", e)))?
+        .ok_or_else(|| BtError::Timeout(format!("opencode timed out after {}s", timeout.as_secs())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_run_with_timeout_maps_errors() {
+        let started = Instant::now();
+        let result = run_with_timeout(Command::new("sleep").arg("30"), Duration::from_millis(300));
+        assert!(matches!(result, Err(BtError::Timeout(_))));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let result = run_with_timeout(
+            &mut Command::new("/nonexistent/opencode"),
+            Duration::from_secs(5),
+        );
+        assert!(matches!(result, Err(BtError::DependencyMissing(_))));
+    }
+
+    #[test]
+    fn test_binary_resolution_and_env() {
+        let mut config = OpencodeConfig {
+            binary: "sh".to_string(),
+            ..OpencodeConfig::default()
+        };
+        assert!(config.resolve_binary().unwrap().ends_with("sh"));
+
+        config.apply(&OpencodeOverrides {
+            binary: Some("/nonexistent/opencode".to_string()),
+            ..Default::default()
+        });
+        assert!(matches!(config.check(), Err(BtError::DependencyMissing(_))));
+
+        config.env = HashMap::from([("GREETING".to_string(), "${HOME}".to_string())]);
+        let output = run_with_timeout(
+            config
+                .command(Path::new("sh"))
+                .unwrap()
+                .args(["-c", "echo $GREETING"]),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            std::env::var("HOME").unwrap()
+        );
+
+        config.env = HashMap::from([("KEY".to_string(), "${BT_TEST_UNSET_VAR}".to_string())]);
+        assert!(matches!(
+            config.command(Path::new("sh")),
+            Err(BtError::DependencyMissing(_))
+        ));
+    }
+}
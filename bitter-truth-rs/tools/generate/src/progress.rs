@@ -0,0 +1,95 @@
+// Liveness logs and a partial-output file for streamed generations
+
+use crate::provider::Params;
+use bt_core::{log_stderr, LogEntry};
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// How often a streaming call reports progress on stderr
+const INTERVAL: Duration = Duration::from_secs(5);
+
+/// Tracks text as it streams in, so watchers see a long generation is alive
+pub struct Progress {
+    provider: &'static str,
+    trace_id: String,
+    started: Instant,
+    last_log: Instant,
+    chars: usize,
+    partial: Option<File>,
+}
+
+impl Progress {
+    /// Start tracking; truncates `params.partial_path` so a retry starts clean
+    pub fn new(provider: &'static str, params: &Params) -> Self {
+        let partial = params.partial_path.and_then(|path| File::create(path).ok());
+        Self {
+            provider,
+            trace_id: params.trace_id.to_string(),
+            started: Instant::now(),
+            last_log: Instant::now(),
+            chars: 0,
+            partial,
+        }
+    }
+
+    pub fn push(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.chars += text.chars().count();
+        if let Some(file) = &mut self.partial {
+            // Best effort: the partial file is only for watching
+            let _ = file.write_all(text.as_bytes()).and_then(|_| file.flush());
+        }
+        if self.last_log.elapsed() >= INTERVAL {
+            self.last_log = Instant::now();
+            self.log("generation in progress");
+        }
+    }
+
+    pub fn finish(self) {
+        self.log("generation stream complete");
+    }
+
+    fn log(&self, msg: &str) {
+        let log = LogEntry::info(msg, self.trace_id.clone())
+            .with_extra(
+                "provider",
+                serde_json::Value::String(self.provider.to_string()),
+            )
+            .with_extra("chars", serde_json::json!(self.chars))
+            .with_extra("approx_tokens", serde_json::json!(self.chars / 4))
+            .with_extra(
+                "elapsed_ms",
+                serde_json::json!(self.started.elapsed().as_millis() as u64),
+            );
+        log_stderr(&log);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_mirrors_text_to_partial_file() {
+        let path =
+            std::env::temp_dir().join(format!("generate-progress-{}.partial", std::process::id()));
+        let params = Params {
+            model: "m",
+            timeout: Duration::from_secs(1),
+            trace_id: "t",
+            partial_path: Some(&path),
+            sampling: crate::provider::GenerationParams::default(),
+        };
+        let mut progress = Progress::new("test", &params);
+        progress.push("fn main() ");
+        progress.push("{}");
+        assert_eq!(progress.chars, 12);
+        progress.finish();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fn main() {}");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+// Prompt templates: minijinja files picked by name, with the built-in prompt as fallback
+
+use bt_core::BtError;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Used when no `default.j2` exists in the templates directory
+pub const BUILTIN: &str = include_str!("../templates/default.j2");
+
+/// Variables every template can use
+#[derive(Debug, Serialize)]
+pub struct PromptVars<'a> {
+    pub language: &'a str,
+    pub task: &'a str,
+    pub contract: &'a str,
+    pub feedback: &'a str,
+    pub attempt: String,
+    /// The caller wants a file tree, so ask for annotated blocks
+    pub multi_file: bool,
+    /// Packed repository files; empty when none were requested
+    pub repo_context: &'a str,
+    /// Edit mode: ask for a diff against `existing` instead of the whole file
+    pub edit: bool,
+    /// Current content of the file in edit mode; may be empty
+    pub existing: &'a str,
+}
+
+/// Load a template by name (`<dir>/<name>.j2`) or by path.
+/// An empty name means `default`, which falls back to [`BUILTIN`].
+pub fn load(name: &str, dir: Option<&Path>) -> Result<String, BtError> {
+    let name = if name.is_empty() { "default" } else { name };
+    let path = if name.contains('/') || name.ends_with(".j2") {
+        Some(PathBuf::from(name))
+    } else {
+        dir.map(|d| d.join(format!("{}.j2", name)))
+    };
+
+    match path {
+        Some(path) if path.exists() => std::fs::read_to_string(&path)
+            .map_err(|e| BtError::Io(format!("Failed to read template {}: {}", path.display(), e))),
+        _ if name == "default" => Ok(BUILTIN.to_string()),
+        Some(path) => Err(BtError::NotFound(format!(
+            "Prompt template not found: {}",
+            path.display()
+        ))),
+        None => Err(BtError::NotFound(format!(
+            "Prompt template '{}' not found (no templates directory)",
+            name
+        ))),
+    }
+}
+
+/// Render a template; unknown variables are errors so typos don't silently vanish
+pub fn render(source: &str, vars: &PromptVars) -> Result<String, BtError> {
+    let mut env = minijinja::Environment::new();
+    env.set_undefined_behavior(minijinja::UndefinedBehavior::Strict);
+    env.render_str(source, vars)
+        .map_err(|e| BtError::InvalidInput(format!("Prompt template error: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> PromptVars<'static> {
+        PromptVars {
+            language: "rust",
+            task: "add numbers",
+            contract: "{}",
+            feedback: "none",
+            attempt: "1/5".to_string(),
+            multi_file: false,
+            repo_context: "",
+            edit: false,
+            existing: "",
+        }
+    }
+
+    #[test]
+    fn test_builtin_template_renders_all_fields() {
+        let prompt = render(&load("", None).unwrap(), &vars()).unwrap();
+        assert!(prompt.starts_with("You are a rust code generator."));
+        assert!(prompt.contains("TASK: add numbers"));
+        assert!(prompt.contains("ATTEMPT: 1/5"));
+        assert!(prompt.ends_with("OUTPUT ONLY THE CODE:"));
+        assert!(prompt.contains("- Output valid, runnable code\n\nGenerate"));
+        assert!(prompt.contains("{}\n\nFEEDBACK"));
+
+        let edit = render(
+            &load("", None).unwrap(),
+            &PromptVars {
+                edit: true,
+                existing: "fn main() {}",
+                ..vars()
+            },
+        )
+        .unwrap();
+        assert!(edit.contains("CURRENT FILE (change only what the task and feedback require):\nfn main() {}\n\nFEEDBACK"));
+        assert!(edit.ends_with("OUTPUT ONLY THE DIFF:"));
+        assert!(edit.starts_with("You are a rust code editor. Output ONLY a unified diff"));
+        assert!(!edit.contains("Output ONLY valid rust code"));
+
+        // An empty file is still edited, not regenerated
+        let empty = render(
+            &load("", None).unwrap(),
+            &PromptVars {
+                edit: true,
+                ..vars()
+            },
+        )
+        .unwrap();
+        assert!(empty.contains(
+            "CURRENT FILE (change only what the task and feedback require):\n(empty file)\n"
+        ));
+        assert!(empty.ends_with("OUTPUT ONLY THE DIFF:"));
+
+        assert!(load("missing", None).is_err());
+        assert!(render("{{ tsak }}", &vars()).is_err());
+    }
+}
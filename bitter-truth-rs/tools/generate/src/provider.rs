@@ -0,0 +1,329 @@
+// Model provider abstraction: one trait, a registry keyed by name, and a retrying caller
+
+use crate::{anthropic, ollama, openai, opencode};
+use bt_core::usage::Usage;
+use bt_core::{log_stderr, BtError, ErrorCode, LogEntry, RetryPolicy, Span};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Names accepted by `provider`, in registry order
+pub const PROVIDERS: &[&str] = &["opencode", "openai", "ollama", "anthropic"];
+
+/// Per-call parameters shared by every provider
+#[derive(Debug, Clone)]
+pub struct Params<'a> {
+    pub model: &'a str,
+    pub timeout: Duration,
+    pub trace_id: &'a str,
+    /// Where streaming providers mirror text as it arrives
+    pub partial_path: Option<&'a Path>,
+    pub sampling: GenerationParams,
+}
+
+/// Sampling parameters forwarded to the provider; unset fields use the provider's defaults
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationParams {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub seed: Option<u64>,
+    pub top_p: Option<f32>,
+}
+
+impl GenerationParams {
+    /// Fill unset fields from `defaults`
+    pub fn or(&self, defaults: &GenerationParams) -> GenerationParams {
+        GenerationParams {
+            temperature: self.temperature.or(defaults.temperature),
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            seed: self.seed.or(defaults.seed),
+            top_p: self.top_p.or(defaults.top_p),
+        }
+    }
+}
+
+/// What a provider produced for one prompt
+#[derive(Debug, Clone, Default)]
+pub struct Generation {
+    pub text: String,
+    pub usage: Usage,
+}
+
+pub trait Provider {
+    fn name(&self) -> &'static str;
+
+    /// Provider-specific model, preferred over the global default
+    fn model(&self) -> Option<&str>;
+
+    fn settings(&self) -> &CallSettings;
+
+    /// Sampling defaults from the provider's config table
+    fn defaults(&self) -> &GenerationParams;
+
+    /// Fail fast, before any prompt is built, when the provider cannot run at all
+    fn check(&self) -> Result<(), BtError> {
+        Ok(())
+    }
+
+    fn generate(&self, prompt: &str, params: &Params) -> Result<Generation, BtError>;
+}
+
+/// Timeout and retry settings, flattened into each `[generate.<provider>]` table
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CallSettings {
+    /// Per-attempt timeout; never runs past the context timeout
+    pub timeout_seconds: Option<u64>,
+    /// Retries for transient failures (timeouts, rate limits, 5xx)
+    pub retry: RetryPolicy,
+}
+
+impl Default for CallSettings {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: None,
+            retry: RetryPolicy {
+                max_attempts: 3,
+                retry_on: vec![ErrorCode::Timeout, ErrorCode::Io],
+                ..RetryPolicy::default()
+            },
+        }
+    }
+}
+
+/// Every provider's config table, flattened into `[generate]`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Registry {
+    pub opencode: opencode::OpencodeConfig,
+    pub openai: openai::OpenAiConfig,
+    pub ollama: ollama::OllamaConfig,
+    pub anthropic: anthropic::AnthropicConfig,
+}
+
+impl Registry {
+    pub fn get(&self, name: &str) -> Result<&dyn Provider, BtError> {
+        match name {
+            "opencode" => Ok(&self.opencode),
+            "openai" => Ok(&self.openai),
+            "ollama" => Ok(&self.ollama),
+            "anthropic" => Ok(&self.anthropic),
+            other => Err(BtError::InvalidInput(format!(
+                "Unknown provider '{}' (expected one of: {})",
+                other,
+                PROVIDERS.join(", ")
+            ))),
+        }
+    }
+
+    /// Point every HTTP provider at `url`
+    pub fn set_base_url(&mut self, url: &str) {
+        self.openai.base_url = url.to_string();
+        self.ollama.base_url = url.to_string();
+        self.anthropic.base_url = url.to_string();
+    }
+}
+
+/// Run `prompt` through `provider`, retrying transient failures per its settings.
+///
+/// `params.timeout` is one deadline for every attempt together, so retrying a
+/// hung provider cannot stretch the call past the context timeout.
+pub fn call(provider: &dyn Provider, prompt: &str, params: &Params) -> Result<Generation, BtError> {
+    let settings = provider.settings();
+    let trace_id = params.trace_id;
+    let deadline = Instant::now() + params.timeout;
+
+    let mut attempt = 1;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let params = Params {
+            timeout: settings
+                .timeout_seconds
+                .map(Duration::from_secs)
+                .map_or(remaining, |t| t.min(remaining)),
+            sampling: params.sampling.or(provider.defaults()),
+            ..params.clone()
+        };
+        let result = {
+            let _span = Span::enter(provider.name(), trace_id);
+            provider.generate(prompt, &params)
+        };
+        match result {
+            Ok(generation) => return Ok(generation),
+            Err(e)
+                if settings.retry.should_retry(attempt, e.code())
+                    && Instant::now() + settings.retry.delay_for(attempt) < deadline =>
+            {
+                let delay = settings.retry.delay_for(attempt);
+                let log = LogEntry::warn("provider call failed, retrying", trace_id.to_string())
+                    .with_extra(
+                        "provider",
+                        serde_json::Value::String(provider.name().to_string()),
+                    )
+                    .with_extra(
+                        "attempt",
+                        serde_json::Value::String(settings.retry.attempt_label(attempt)),
+                    )
+                    .with_extra("delay_ms", serde_json::json!(delay.as_millis() as u64))
+                    .with_extra("error", serde_json::Value::String(e.message().to_string()));
+                log_stderr(&log);
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct Flaky {
+        failures: Cell<u32>,
+        settings: CallSettings,
+        defaults: GenerationParams,
+    }
+
+    impl Provider for Flaky {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+        fn model(&self) -> Option<&str> {
+            None
+        }
+        fn settings(&self) -> &CallSettings {
+            &self.settings
+        }
+        fn defaults(&self) -> &GenerationParams {
+            &self.defaults
+        }
+        fn generate(&self, _prompt: &str, _params: &Params) -> Result<Generation, BtError> {
+            if self.failures.get() > 0 {
+                self.failures.set(self.failures.get() - 1);
+                return Err(BtError::Io("503".to_string()));
+            }
+            Ok(Generation {
+                text: "ok".to_string(),
+                ..Generation::default()
+            })
+        }
+    }
+
+    #[test]
+    fn test_call_retries_transient_failures_up_to_max_attempts() {
+        let mut settings = CallSettings::default();
+        settings.retry.backoff = bt_core::retry::Backoff::Fixed { delay_ms: 0 };
+
+        let params = Params {
+            model: "m",
+            timeout: Duration::from_secs(1),
+            trace_id: "t",
+            partial_path: None,
+            sampling: GenerationParams::default(),
+        };
+
+        let provider = Flaky {
+            failures: Cell::new(2),
+            settings: settings.clone(),
+            defaults: GenerationParams::default(),
+        };
+        assert_eq!(call(&provider, "p", &params).unwrap().text, "ok");
+
+        let provider = Flaky {
+            failures: Cell::new(3),
+            settings,
+            defaults: GenerationParams::default(),
+        };
+        assert!(matches!(call(&provider, "p", &params), Err(BtError::Io(_))));
+    }
+
+    /// Never answers; reports a timeout once its per-attempt budget is spent
+    struct Hung {
+        calls: Cell<u32>,
+        settings: CallSettings,
+        defaults: GenerationParams,
+    }
+
+    impl Provider for Hung {
+        fn name(&self) -> &'static str {
+            "hung"
+        }
+        fn model(&self) -> Option<&str> {
+            None
+        }
+        fn settings(&self) -> &CallSettings {
+            &self.settings
+        }
+        fn defaults(&self) -> &GenerationParams {
+            &self.defaults
+        }
+        fn generate(&self, _prompt: &str, params: &Params) -> Result<Generation, BtError> {
+            self.calls.set(self.calls.get() + 1);
+            std::thread::sleep(params.timeout);
+            Err(BtError::Timeout("no reply".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_call_retries_share_one_deadline() {
+        let mut settings = CallSettings::default();
+        settings.retry.backoff = bt_core::retry::Backoff::Fixed { delay_ms: 0 };
+        let provider = Hung {
+            calls: Cell::new(0),
+            settings,
+            defaults: GenerationParams::default(),
+        };
+        let params = Params {
+            model: "m",
+            timeout: Duration::from_millis(300),
+            trace_id: "t",
+            partial_path: None,
+            sampling: GenerationParams::default(),
+        };
+
+        let started = Instant::now();
+        assert!(matches!(
+            call(&provider, "p", &params),
+            Err(BtError::Timeout(_))
+        ));
+        assert!(started.elapsed() < Duration::from_millis(600));
+        assert_eq!(provider.calls.get(), 1);
+    }
+
+    #[test]
+    fn test_registry_reads_flattened_provider_tables() {
+        let toml = "[generate.ollama]\nmodel = \"qwen\"\ntimeout_seconds = 30\n[generate.ollama.retry]\nmax_attempts = 1\n";
+        let registry =
+            bt_core::config::section_from_toml_str::<Registry>(toml, "generate").unwrap();
+        let ollama = registry.get("ollama").unwrap();
+        assert_eq!(ollama.model(), Some("qwen"));
+        assert_eq!(ollama.settings().timeout_seconds, Some(30));
+        assert_eq!(ollama.settings().retry.max_attempts, 1);
+        assert!(registry.get("gpt").is_err());
+    }
+
+    #[test]
+    fn test_input_params_override_provider_defaults() {
+        let toml = "[generate.openai]\ntemperature = 0.7\nmax_tokens = 512\n";
+        let registry =
+            bt_core::config::section_from_toml_str::<Registry>(toml, "generate").unwrap();
+        let input = GenerationParams {
+            temperature: Some(0.0),
+            seed: Some(7),
+            ..GenerationParams::default()
+        };
+        let merged = input.or(registry.get("openai").unwrap().defaults());
+        assert_eq!(
+            merged,
+            GenerationParams {
+                temperature: Some(0.0),
+                max_tokens: Some(512),
+                seed: Some(7),
+                top_p: None
+            }
+        );
+    }
+}
@@ -0,0 +1,220 @@
+// Repository context packing: rank matching files and fit them into a prompt budget
+
+use bt_core::BtError;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+const SKIP_DIRS: &[&str] = &["target", "node_modules", "__pycache__", "vendor", "dist"];
+
+/// Which repo files to show the model, and how much of them
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ContextSpec {
+    /// Directory the globs are relative to
+    pub root: String,
+    /// Globs such as `contracts/**/*.yaml` or `src/**/*.rs`
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Total bytes of file content to pack
+    pub max_bytes: usize,
+    /// Longer files are cut to this many bytes
+    pub max_file_bytes: usize,
+}
+
+impl Default for ContextSpec {
+    fn default() -> Self {
+        Self {
+            root: ".".to_string(),
+            include: vec![],
+            exclude: vec![],
+            max_bytes: 32_000,
+            max_file_bytes: 8_000,
+        }
+    }
+}
+
+/// The packed prompt section and the files it includes
+#[derive(Debug, Default)]
+pub struct Packed {
+    pub text: String,
+    pub files: Vec<String>,
+}
+
+/// Collect files matching the spec, most relevant to `task` first, until the budget runs out
+pub fn pack(spec: &ContextSpec, task: &str, language: &str) -> Result<Packed, BtError> {
+    if spec.include.is_empty() {
+        return Ok(Packed::default());
+    }
+    let patterns = |globs: &[String]| {
+        globs
+            .iter()
+            .map(|g| {
+                glob::Pattern::new(g).map_err(|e| {
+                    BtError::InvalidInput(format!("Invalid context glob '{}': {}", g, e))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let include = patterns(&spec.include)?;
+    let exclude = patterns(&spec.exclude)?;
+
+    let root = PathBuf::from(&spec.root);
+    if !root.is_dir() {
+        return Err(BtError::NotFound(format!(
+            "Context root not found: {}",
+            spec.root
+        )));
+    }
+    let mut paths = vec![];
+    walk(&root, &mut paths);
+
+    let keywords = keywords(task);
+    let mut candidates: Vec<(usize, String, String)> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let rel = path.strip_prefix(&root).ok()?.to_path_buf();
+            let matches = |set: &[glob::Pattern]| set.iter().any(|p| p.matches_path(&rel));
+            if !matches(&include) || matches(&exclude) {
+                return None;
+            }
+            // Unreadable or binary files are skipped
+            let content = std::fs::read_to_string(&path).ok()?;
+            let rel = rel.display().to_string();
+            Some((score(&rel, &content, &keywords, language), rel, content))
+        })
+        .collect();
+    candidates.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then(a.2.len().cmp(&b.2.len()))
+            .then(a.1.cmp(&b.1))
+    });
+
+    let mut packed = Packed::default();
+    let mut used = 0;
+    for (_, rel, content) in candidates {
+        let body = truncate(&content, spec.max_file_bytes);
+        if used + body.len() > spec.max_bytes {
+            continue;
+        }
+        used += body.len();
+        let marker = if body.len() < content.len() {
+            " (truncated)"
+        } else {
+            ""
+        };
+        packed
+            .text
+            .push_str(&format!("--- {}{} ---\n{}\n", rel, marker, body.trim_end()));
+        packed.files.push(rel);
+    }
+    Ok(packed)
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIP_DIRS.contains(&name.as_str()) {
+                walk(&path, files);
+            }
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+/// Distinct lowercase words from the task worth matching on
+fn keywords(task: &str) -> Vec<String> {
+    let mut words: Vec<String> = task
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| w.len() >= 4)
+        .map(str::to_lowercase)
+        .collect();
+    words.sort();
+    words.dedup();
+    words
+}
+
+/// Task words in the path count most, then in the content; same-language files get a nudge
+fn score(rel: &str, content: &str, keywords: &[String], language: &str) -> usize {
+    let path = rel.to_lowercase();
+    let content = content.to_lowercase();
+    let hits: usize = keywords
+        .iter()
+        .map(|k| {
+            if path.contains(k.as_str()) {
+                3
+            } else if content.contains(k.as_str()) {
+                1
+            } else {
+                0
+            }
+        })
+        .sum();
+    let ext = Path::new(rel)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let same_language = match language {
+        "rust" => ext == "rs",
+        "python" => ext == "py",
+        "typescript" => ext == "ts",
+        "javascript" => ext == "js",
+        "go" => ext == "go",
+        "nushell" => ext == "nu",
+        _ => false,
+    };
+    hits + if same_language { 2 } else { 0 }
+}
+
+fn truncate(content: &str, max: usize) -> &str {
+    if content.len() <= max {
+        return content;
+    }
+    let mut end = max;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    &content[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_ranks_by_task_and_respects_budget() {
+        let root = std::env::temp_dir().join(format!("generate-context-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("contracts")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("contracts/invoice.yaml"), "total: number\n").unwrap();
+        std::fs::write(root.join("contracts/user.yaml"), "name: string\n").unwrap();
+        std::fs::write(root.join("contracts/big.yaml"), "x".repeat(100)).unwrap();
+        std::fs::write(root.join("target/invoice.yaml"), "ignored\n").unwrap();
+
+        let spec = ContextSpec {
+            root: root.display().to_string(),
+            include: vec!["**/*.yaml".to_string()],
+            exclude: vec!["**/user.yaml".to_string()],
+            max_bytes: 60,
+            max_file_bytes: 40,
+        };
+        let packed = pack(&spec, "Sum the invoice lines", "rust").unwrap();
+        assert_eq!(
+            packed.files,
+            vec!["contracts/invoice.yaml", "contracts/big.yaml"]
+        );
+        assert!(packed
+            .text
+            .starts_with("--- contracts/invoice.yaml ---\ntotal: number\n"));
+        assert!(packed
+            .text
+            .contains("--- contracts/big.yaml (truncated) ---"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,324 @@
+// Contract constraints beyond structure: enums, ranges, lengths, patterns and cross-field rules
+
+use crate::issue::Code;
+use regex::Regex;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Per-field constraints, read from the contract's field definition
+#[derive(Debug, Clone, Default)]
+pub struct Constraints {
+    one_of: Vec<Value>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pattern: Option<Regex>,
+}
+
+/// A failed constraint: which one, and the value that broke it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub code: Code,
+    /// e.g. `maximum 120`, `pattern ^[a-z]+$`
+    pub constraint: String,
+    pub value: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} violates {}", self.value, self.constraint)
+    }
+}
+
+impl Constraints {
+    pub fn parse(definition: &Value) -> Result<Self, String> {
+        let number = |key: &str| definition[key].as_f64();
+        let length = |key: &str| definition[key].as_u64().map(|n| n as usize);
+        let pattern = match definition["pattern"].as_str() {
+            Some(pattern) => Some(
+                Regex::new(pattern).map_err(|e| format!("Invalid pattern {:?}: {}", pattern, e))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            one_of: definition["enum"].as_array().cloned().unwrap_or_default(),
+            minimum: number("minimum"),
+            maximum: number("maximum"),
+            exclusive_minimum: number("exclusiveMinimum"),
+            exclusive_maximum: number("exclusiveMaximum"),
+            min_length: length("minLength"),
+            max_length: length("maxLength"),
+            pattern,
+        })
+    }
+
+    /// Every constraint `value` breaks; constraints that don't apply to its type are skipped
+    pub fn check(&self, value: &Value) -> Vec<Violation> {
+        let mut violations = vec![];
+        let mut fail = |code: Code, constraint: String| {
+            violations.push(Violation {
+                code,
+                constraint,
+                value: value.to_string(),
+            })
+        };
+
+        if !self.one_of.is_empty() && !self.one_of.contains(value) {
+            fail(
+                Code::EnumViolation,
+                format!("enum {}", Value::Array(self.one_of.clone())),
+            );
+        }
+        if let Some(n) = value.as_f64() {
+            if self.minimum.is_some_and(|min| n < min) {
+                fail(
+                    Code::RangeViolation,
+                    format!("minimum {}", self.minimum.unwrap_or_default()),
+                );
+            }
+            if self.maximum.is_some_and(|max| n > max) {
+                fail(
+                    Code::RangeViolation,
+                    format!("maximum {}", self.maximum.unwrap_or_default()),
+                );
+            }
+            if self.exclusive_minimum.is_some_and(|min| n <= min) {
+                fail(
+                    Code::RangeViolation,
+                    format!(
+                        "exclusiveMinimum {}",
+                        self.exclusive_minimum.unwrap_or_default()
+                    ),
+                );
+            }
+            if self.exclusive_maximum.is_some_and(|max| n >= max) {
+                fail(
+                    Code::RangeViolation,
+                    format!(
+                        "exclusiveMaximum {}",
+                        self.exclusive_maximum.unwrap_or_default()
+                    ),
+                );
+            }
+        }
+        if let Some(s) = value.as_str() {
+            let length = s.chars().count();
+            if self.min_length.is_some_and(|min| length < min) {
+                fail(
+                    Code::LengthViolation,
+                    format!("minLength {}", self.min_length.unwrap_or_default()),
+                );
+            }
+            if self.max_length.is_some_and(|max| length > max) {
+                fail(
+                    Code::LengthViolation,
+                    format!("maxLength {}", self.max_length.unwrap_or_default()),
+                );
+            }
+            if let Some(pattern) = self.pattern.as_ref().filter(|p| !p.is_match(s)) {
+                fail(
+                    Code::PatternViolation,
+                    format!("pattern {}", pattern.as_str()),
+                );
+            }
+        }
+        violations
+    }
+}
+
+/// A comparison between fields (or a field and a literal) that every record must satisfy,
+/// declared under a model's `rules` as `"end >= start"` or `{name, check}`
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub expression: String,
+    left: Operand,
+    op: Op,
+    right: Operand,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Field(String),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Rule {
+    pub fn parse(definition: &Value) -> Result<Self, String> {
+        let (name, expression) = match definition {
+            Value::String(check) => (check.clone(), check.clone()),
+            other => {
+                let check = other["check"]
+                    .as_str()
+                    .ok_or_else(|| format!("Rule without `check`: {}", other))?;
+                (
+                    other["name"].as_str().unwrap_or(check).to_string(),
+                    check.to_string(),
+                )
+            }
+        };
+        // Two-character operators first so `>=` isn't read as `>`
+        let (at, op, width) = [
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ]
+        .iter()
+        .find_map(|(token, op)| expression.find(token).map(|at| (at, *op, token.len())))
+        .ok_or_else(|| {
+            format!(
+                "Rule {:?} has no comparison (==, !=, <, <=, >, >=)",
+                expression
+            )
+        })?;
+        Ok(Self {
+            name,
+            left: operand(&expression[..at]),
+            op,
+            right: operand(&expression[at + width..]),
+            expression,
+        })
+    }
+
+    /// Offending values as `field=value` pairs when `record` breaks the rule;
+    /// `None` when it holds or a field it compares is absent
+    pub fn check(&self, record: &Value) -> Option<String> {
+        let left = self.left.resolve(record)?;
+        let right = self.right.resolve(record)?;
+        let holds = match self.op {
+            Op::Eq => left == right,
+            Op::Ne => left != right,
+            op => compare(&left, &right).is_some_and(|ordering| match op {
+                Op::Lt => ordering == Ordering::Less,
+                Op::Le => ordering != Ordering::Greater,
+                Op::Gt => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }),
+        };
+        if holds {
+            return None;
+        }
+        let values: Vec<String> = [(&self.left, &left), (&self.right, &right)]
+            .iter()
+            .filter_map(|(operand, value)| match operand {
+                Operand::Field(name) => Some(format!("{}={}", name, value)),
+                Operand::Literal(_) => None,
+            })
+            .collect();
+        Some(values.join(", "))
+    }
+}
+
+impl Operand {
+    fn resolve(&self, record: &Value) -> Option<Value> {
+        match self {
+            Operand::Literal(value) => Some(value.clone()),
+            Operand::Field(path) => path
+                .split('.')
+                .try_fold(record, |value, key| value.get(key))
+                .filter(|v| !v.is_null())
+                .cloned(),
+        }
+    }
+}
+
+/// A literal (number, quoted string, `true`, `false`, `null`) or a dotted field path
+fn operand(text: &str) -> Operand {
+    let text = text.trim();
+    let quoted = (text.starts_with('\'') && text.ends_with('\''))
+        || (text.starts_with('"') && text.ends_with('"'));
+    if quoted && text.len() >= 2 {
+        return Operand::Literal(Value::String(text[1..text.len() - 1].to_string()));
+    }
+    match serde_json::from_str::<Value>(text) {
+        Ok(value) if !value.is_object() && !value.is_array() => Operand::Literal(value),
+        _ => Operand::Field(text.to_string()),
+    }
+}
+
+/// Numbers compare numerically, strings lexically (so ISO dates order correctly)
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.as_f64()?.partial_cmp(&r.as_f64()?),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_field_constraints_report_offending_values() {
+        let constraints = Constraints::parse(
+            &json!({"enum": ["ok", "failed"], "maxLength": 4, "pattern": "^[a-z]+$"}),
+        )
+        .unwrap();
+        assert!(constraints.check(&json!("ok")).is_empty());
+        let violations: Vec<String> = constraints
+            .check(&json!("Broken"))
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                "\"Broken\" violates enum [\"ok\",\"failed\"]",
+                "\"Broken\" violates maxLength 4",
+                "\"Broken\" violates pattern ^[a-z]+$",
+            ]
+        );
+
+        let range = Constraints::parse(&json!({"minimum": 0, "exclusiveMaximum": 120})).unwrap();
+        assert_eq!(range.check(&json!(-1))[0].constraint, "minimum 0");
+        assert_eq!(
+            range.check(&json!(120))[0].constraint,
+            "exclusiveMaximum 120"
+        );
+        assert_eq!(range.check(&json!(120))[0].code, Code::RangeViolation);
+        assert!(range.check(&json!(30)).is_empty());
+        assert!(Constraints::parse(&json!({"pattern": "("})).is_err());
+    }
+
+    #[test]
+    fn test_cross_field_rules() {
+        let rule =
+            Rule::parse(&json!({"name": "ends_after_start", "check": "end >= start"})).unwrap();
+        assert_eq!(
+            rule.check(&json!({"start": "2024-01-01", "end": "2024-02-01"})),
+            None
+        );
+        assert_eq!(
+            rule.check(&json!({"start": 5, "end": 1})),
+            Some("end=1, start=5".to_string())
+        );
+        assert_eq!(rule.check(&json!({"start": 5})), None);
+
+        let literal = Rule::parse(&json!("status != 'failed'")).unwrap();
+        assert_eq!(literal.name, "status != 'failed'");
+        assert_eq!(
+            literal.check(&json!({"status": "failed"})),
+            Some("status=\"failed\"".to_string())
+        );
+        assert!(Rule::parse(&json!("just words")).is_err());
+    }
+}
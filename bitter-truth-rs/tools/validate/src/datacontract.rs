@@ -0,0 +1,275 @@
+// datacontract-cli integration: `datacontract lint` and `datacontract test` against the output
+
+use bt_core::BtError;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Server name injected into the contract copy that points at the output file
+const OUTPUT_SERVER: &str = "bt-output";
+
+/// `[validate.datacontract]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DatacontractConfig {
+    /// Run the CLI for `datacontract.yaml` contracts; `datacontract` in the input overrides this
+    pub enabled: bool,
+    /// Binary name looked up on PATH, or a path to it
+    pub binary: String,
+    /// Run `datacontract test` after `lint`
+    pub test: bool,
+    /// Test this server from the contract instead of the output file
+    pub server: Option<String>,
+}
+
+impl Default for DatacontractConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            binary: "datacontract".to_string(),
+            test: true,
+            server: None,
+        }
+    }
+}
+
+/// A Data Contract Specification file, by name or by its top-level marker
+pub fn is_datacontract(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    if name.ends_with("datacontract.yaml") || name.ends_with("datacontract.yml") {
+        return true;
+    }
+    std::fs::read_to_string(path).is_ok_and(|s| {
+        s.lines()
+            .any(|l| l.starts_with("dataContractSpecification:"))
+    })
+}
+
+/// Lint the contract, then test `output` against it, both within `timeout`.
+/// Returns one message per failed check; empty when everything passed.
+pub fn check(
+    config: &DatacontractConfig,
+    contract: &Path,
+    output: &Path,
+    trace_id: &str,
+    timeout: Option<Duration>,
+) -> Result<Vec<String>, BtError> {
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let run = |subcommand: &str, args: &[&str]| run(config, subcommand, args, deadline);
+    let contract_arg = contract.display().to_string();
+    let mut errors = run("lint", &[&contract_arg])?;
+    if !config.test || !errors.is_empty() {
+        return Ok(errors);
+    }
+
+    match &config.server {
+        Some(server) => errors.extend(run("test", &[&contract_arg, "--server", server])?),
+        None => {
+            let copy = with_output_server(contract, output, trace_id)?;
+            let copy_arg = copy.display().to_string();
+            let result = run("test", &[&copy_arg, "--server", OUTPUT_SERVER]);
+            let _ = std::fs::remove_file(&copy);
+            errors.extend(result?);
+        }
+    }
+    Ok(errors)
+}
+
+/// Run one subcommand, killed at `deadline`; a nonzero exit with nothing
+/// parseable still yields an error
+fn run(
+    config: &DatacontractConfig,
+    subcommand: &str,
+    args: &[&str],
+    deadline: Option<Instant>,
+) -> Result<Vec<String>, BtError> {
+    let mut command = Command::new(&config.binary);
+    command.arg(subcommand).args(args);
+    let timeout = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+    let output = bt_core::process::output_with_timeout(&mut command, timeout)
+        .map_err(|e| {
+            BtError::DependencyMissing(format!(
+                "datacontract-cli ('{}') not available: {}; install it with `pip install datacontract-cli`",
+                config.binary, e
+            ))
+        })?
+        .ok_or_else(|| BtError::Timeout(format!("datacontract {} timed out", subcommand)))?;
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let mut errors = parse(subcommand, &text);
+    if !output.status.success() && errors.is_empty() {
+        let last = text
+            .lines()
+            .map(str::trim)
+            .rfind(|l| !l.is_empty())
+            .unwrap_or("no output");
+        errors.push(format!("datacontract {} failed: {}", subcommand, last));
+    }
+    Ok(errors)
+}
+
+/// Failed rows of the results table, else the numbered list after
+/// "found the following errors"
+fn parse(subcommand: &str, text: &str) -> Vec<String> {
+    let rows: Vec<String> = text
+        .lines()
+        .filter_map(|line| {
+            let cells: Vec<&str> = line.split(['│', '|']).map(str::trim).collect();
+            // ["", result, check, field, details, ""]
+            let result = cells.get(1)?;
+            if !matches!(*result, "failed" | "error" | "warning") {
+                return None;
+            }
+            let check = cells.get(2).copied().unwrap_or_default();
+            let mut message = format!("datacontract {}: {}: {}", subcommand, result, check);
+            if let Some(field) = cells.get(3).filter(|f| !f.is_empty()) {
+                message.push_str(&format!(" ({})", field));
+            }
+            if let Some(details) = cells.get(4).filter(|d| !d.is_empty()) {
+                message.push_str(&format!(": {}", details));
+            }
+            Some(message)
+        })
+        .collect();
+    if !rows.is_empty() {
+        return rows;
+    }
+
+    text.lines()
+        .skip_while(|l| !l.contains("found the following errors"))
+        .skip(1)
+        .filter_map(|l| {
+            let (number, rest) = l.trim().split_once(')')?;
+            number.parse::<u32>().ok()?;
+            Some(format!("datacontract {}: {}", subcommand, rest.trim()))
+        })
+        .collect()
+}
+
+/// Copy the contract with an extra local server reading `output`, so `test`
+/// checks the generated data rather than whatever the contract's servers hold
+fn with_output_server(contract: &Path, output: &Path, trace_id: &str) -> Result<PathBuf, BtError> {
+    let text = std::fs::read_to_string(contract).map_err(|e| {
+        BtError::Io(format!(
+            "Failed to read contract {}: {}",
+            contract.display(),
+            e
+        ))
+    })?;
+    let mut doc: serde_yaml::Value = serde_yaml::from_str(&text).map_err(|e| {
+        BtError::InvalidInput(format!(
+            "Contract {} is not valid YAML: {}",
+            contract.display(),
+            e
+        ))
+    })?;
+
+    let format = output
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("json")
+        .to_lowercase();
+    let server = serde_yaml::to_value(serde_json::json!({
+        "type": "local",
+        "path": std::fs::canonicalize(output).unwrap_or_else(|_| output.to_path_buf()),
+        "format": format,
+    }))
+    .map_err(|e| BtError::Internal(format!("Server entry: {}", e)))?;
+
+    let mapping = doc.as_mapping_mut().ok_or_else(|| {
+        BtError::InvalidInput(format!("Contract {} is not a mapping", contract.display()))
+    })?;
+    let servers = mapping
+        .entry("servers".into())
+        .or_insert_with(|| serde_yaml::Value::Mapping(Default::default()));
+    if let Some(servers) = servers.as_mapping_mut() {
+        servers.insert(OUTPUT_SERVER.into(), server);
+    }
+
+    let copy = std::env::temp_dir().join(format!(
+        "bt-validate-{}-{}.datacontract.yaml",
+        bt_core::output::file_stem(trace_id),
+        std::process::id()
+    ));
+    let yaml = serde_yaml::to_string(&doc)
+        .map_err(|e| BtError::Internal(format!("Contract copy: {}", e)))?;
+    std::fs::write(&copy, yaml)
+        .map_err(|e| BtError::Io(format!("Failed to write {}: {}", copy.display(), e)))?;
+    Ok(copy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_results_table_and_error_list() {
+        let table = "Testing /tmp/c.yaml\n\
+╭────────┬──────────────────────────┬──────────┬─────────╮\n\
+│ Result │ Check                    │ Field    │ Details │\n\
+├────────┼──────────────────────────┼──────────┼─────────┤\n\
+│ passed │ Check that field is present │ orders.id │      │\n\
+│ failed │ Check that field is required │ orders.total │ 2 missing values │\n\
+╰────────┴──────────────────────────┴──────────┴─────────╯\n";
+        assert_eq!(
+            parse("test", table),
+            vec!["datacontract test: failed: Check that field is required (orders.total): 2 missing values"]
+        );
+
+        let lint = "🔴 data contract is invalid, found the following errors:\n1) models.orders: type is required\n2) info.title: missing\n";
+        assert_eq!(
+            parse("lint", lint),
+            vec![
+                "datacontract lint: models.orders: type is required",
+                "datacontract lint: info.title: missing"
+            ]
+        );
+        assert!(parse("lint", "🟢 data contract is valid. Run 1 checks.").is_empty());
+    }
+
+    #[test]
+    fn test_output_server_is_injected() {
+        let dir = std::env::temp_dir().join(format!("validate-dc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let contract = dir.join("datacontract.yaml");
+        std::fs::write(
+            &contract,
+            "dataContractSpecification: 1.1.0\nid: orders\nservers:\n  prod:\n    type: s3\n",
+        )
+        .unwrap();
+        assert!(is_datacontract(&contract));
+
+        // The trace id comes from the caller and must not steer the copy out of the temp dir
+        let copy = with_output_server(&contract, &dir.join("out.csv"), "../../t").unwrap();
+        assert_eq!(copy.parent(), Some(std::env::temp_dir().as_path()));
+        let doc: serde_yaml::Value =
+            serde_yaml::from_str(&std::fs::read_to_string(&copy).unwrap()).unwrap();
+        assert_eq!(doc["servers"]["bt-output"]["format"], "csv");
+        assert_eq!(doc["servers"]["prod"]["type"], "s3");
+
+        std::fs::remove_file(copy).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_stuck_cli_times_out() {
+        let config = DatacontractConfig {
+            binary: "sleep".to_string(),
+            ..DatacontractConfig::default()
+        };
+        let started = Instant::now();
+        let deadline = Some(started + Duration::from_millis(300));
+        assert!(matches!(
+            run(&config, "30", &[], deadline),
+            Err(BtError::Timeout(_))
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
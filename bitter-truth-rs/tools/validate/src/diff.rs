@@ -0,0 +1,111 @@
+// Expected-vs-actual diff of a failed validation, and a summary written for retry feedback
+
+use serde::Serialize;
+use std::fmt;
+
+/// Entries listed per section of the summary before it says how many more there are
+const LIMIT: usize = 20;
+
+/// How the output differs from the contract
+#[derive(Debug, Default, Serialize)]
+pub struct Diff {
+    /// Paths of required fields (or columns) the output lacks
+    pub missing: Vec<String>,
+    /// Paths present in the output that the contract does not declare
+    pub unexpected: Vec<String>,
+    pub mismatches: Vec<Mismatch>,
+    /// Every other problem (constraints, rules, decode failures), as reported in `errors`
+    pub other: Vec<String>,
+}
+
+/// A value whose type is not the one the contract declares
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Mismatch {
+    pub path: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} must be {}, got {}",
+            self.path, self.expected, self.actual
+        )
+    }
+}
+
+impl Diff {
+    /// Plain-text account of the diff, one section per kind of problem, phrased
+    /// as what to change so it can be passed to the model as retry feedback
+    pub fn summary(&self) -> String {
+        let problems =
+            self.missing.len() + self.unexpected.len() + self.mismatches.len() + self.other.len();
+        let mut lines = vec![format!(
+            "Output does not match the contract ({} problem{}).",
+            problems,
+            if problems == 1 { "" } else { "s" }
+        )];
+        let mismatches: Vec<String> = self.mismatches.iter().map(Mismatch::to_string).collect();
+        section(
+            &mut lines,
+            "Missing required fields (add them)",
+            &self.missing,
+        );
+        section(&mut lines, "Wrong types (change the values)", &mismatches);
+        section(
+            &mut lines,
+            "Unexpected fields (not in the contract; remove them)",
+            &self.unexpected,
+        );
+        section(&mut lines, "Other problems", &self.other);
+        lines.join("\n")
+    }
+}
+
+fn section(lines: &mut Vec<String>, title: &str, entries: &[String]) {
+    if entries.is_empty() {
+        return;
+    }
+    lines.push(format!("{}:", title));
+    lines.extend(
+        entries
+            .iter()
+            .take(LIMIT)
+            .map(|entry| format!("- {}", entry)),
+    );
+    if entries.len() > LIMIT {
+        lines.push(format!("- ... and {} more", entries.len() - LIMIT));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_sections() {
+        let diff = Diff {
+            missing: vec!["result".to_string()],
+            unexpected: vec!["debug".to_string()],
+            mismatches: vec![Mismatch {
+                path: "length".to_string(),
+                expected: "integer".to_string(),
+                actual: "string".to_string(),
+            }],
+            other: (0..22)
+                .map(|i| format!("codes[{}]: \"x\" violates pattern ^[A-Z]+$", i))
+                .collect(),
+        };
+        let summary = diff.summary();
+        assert!(summary.starts_with(
+            "Output does not match the contract (25 problems).\n\
+             Missing required fields (add them):\n- result\n\
+             Wrong types (change the values):\n- length must be integer, got string\n\
+             Unexpected fields (not in the contract; remove them):\n- debug\n\
+             Other problems:\n- codes[0]"
+        ));
+        assert!(summary.ends_with("- ... and 2 more"));
+    }
+}
@@ -0,0 +1,128 @@
+// Contracts and outputs in JSON, YAML or TOML, loaded into one JSON value model
+
+use bt_core::BtError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+    /// Tabular formats, checked row by row rather than loaded as a document
+    Csv,
+    Parquet,
+}
+
+impl Format {
+    /// Format implied by the file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "csv" | "tsv" => Some(Format::Csv),
+            "parquet" | "pq" => Some(Format::Parquet),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Json => "JSON",
+            Format::Yaml => "YAML",
+            Format::Toml => "TOML",
+            Format::Csv => "CSV",
+            Format::Parquet => "Parquet",
+        }
+    }
+
+    pub fn is_tabular(self) -> bool {
+        matches!(self, Format::Csv | Format::Parquet)
+    }
+
+    pub fn parse(self, text: &str) -> Result<serde_json::Value, String> {
+        match self {
+            Format::Json => serde_json::from_str(text).map_err(|e| e.to_string()),
+            Format::Yaml => serde_yaml::from_str(text).map_err(|e| e.to_string()),
+            Format::Toml => toml::from_str(text).map_err(|e| e.to_string()),
+            Format::Csv | Format::Parquet => {
+                Err(format!("{} is not a document format", self.name()))
+            }
+        }
+    }
+}
+
+/// A parsed document and the format it was read as
+#[derive(Debug)]
+pub struct Document {
+    pub value: serde_json::Value,
+    pub format: Format,
+}
+
+/// Read `path` as `format`, else as its extension says, else as whichever of
+/// JSON, YAML and TOML parses first. A file that does not parse is an error
+/// message for the caller to report, not a tool failure.
+pub fn load(path: &Path, format: Option<Format>) -> Result<Result<Document, String>, BtError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| BtError::Io(format!("Failed to read {}: {}", path.display(), e)))?;
+    let name = path.display();
+
+    if let Some(format) = format.or_else(|| Format::from_path(path)) {
+        return Ok(format
+            .parse(&text)
+            .map(|value| Document { value, format })
+            .map_err(|e| format!("{} is not valid {}: {}", name, format.name(), e)));
+    }
+    for format in [Format::Json, Format::Yaml, Format::Toml] {
+        // Any plain scalar is valid YAML; only structured documents count as a sniffed match
+        if let Some(value) = format
+            .parse(&text)
+            .ok()
+            .filter(|v| v.is_object() || v.is_array())
+        {
+            return Ok(Ok(Document { value, format }));
+        }
+    }
+    Ok(Err(format!("{} is not valid JSON, YAML or TOML", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_by_extension_flag_and_sniffing() {
+        let dir = std::env::temp_dir().join(format!("validate-doc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, text: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, text).unwrap();
+            path
+        };
+
+        let toml = load(&write("out.toml", "result = \"Hi\"\nlength = 2\n"), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(toml.format, Format::Toml);
+        assert_eq!(toml.value, serde_json::json!({"result": "Hi", "length": 2}));
+
+        let yaml = load(&write("out.data", "result: Hi\n"), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(yaml.format, Format::Yaml);
+        assert_eq!(yaml.value["result"], "Hi");
+
+        let forced = load(&write("out.txt", "{\"a\": 1}"), Some(Format::Json))
+            .unwrap()
+            .unwrap();
+        assert_eq!(forced.value["a"], 1);
+
+        assert!(load(&write("bad.json", "{"), None).unwrap().is_err());
+        assert!(load(&write("plain.out", "just text"), None)
+            .unwrap()
+            .is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+// Validation failures with stable codes, so callers can branch without parsing messages
+
+use serde::Serialize;
+
+/// What kind of failure an issue is; the serialized names are stable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Code {
+    /// A required field or column is absent or null
+    SchemaMissingField,
+    TypeMismatch,
+    /// A value outside a declared enum, or a protobuf enum number the enum does not define
+    EnumViolation,
+    /// `minimum`, `maximum` and their exclusive forms
+    RangeViolation,
+    /// `minLength`, `maxLength`
+    LengthViolation,
+    PatternViolation,
+    /// A model's cross-field rule
+    RuleViolation,
+    /// The output could not be read as its format
+    ParseError,
+    /// datacontract-cli reported a failure
+    DatacontractFailed,
+    /// The contract or output file does not exist
+    FileNotFound,
+}
+
+/// Whether fixing the issue means changing the generated output or the environment it ran in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Structural,
+    Environmental,
+}
+
+impl Code {
+    pub fn category(self) -> Category {
+        match self {
+            Code::FileNotFound => Category::Environmental,
+            _ => Category::Structural,
+        }
+    }
+}
+
+/// One validation failure; `message` is the same text listed in `errors`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Issue {
+    pub code: Code,
+    pub category: Category,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn new(code: Code, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            category: code.category(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Messages alone, for asserting on checker output
+#[cfg(test)]
+pub fn messages(issues: &[Issue]) -> Vec<&str> {
+    issues.iter().map(|issue| issue.message.as_str()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_serialize_stably() {
+        let issue = Issue::new(
+            Code::SchemaMissingField,
+            "result: required field is missing",
+        );
+        assert_eq!(
+            serde_json::to_value(&issue).unwrap(),
+            serde_json::json!({"code": "SCHEMA_MISSING_FIELD", "category": "structural", "message": "result: required field is missing"})
+        );
+        assert_eq!(
+            Issue::new(Code::FileNotFound, "gone").category,
+            Category::Environmental
+        );
+        assert_eq!(
+            serde_json::to_value(Code::DatacontractFailed).unwrap(),
+            "DATACONTRACT_FAILED"
+        );
+    }
+}
@@ -1,40 +1,83 @@
-use bt_core::{error_exit, log_stderr, success_exit, Context, LogEntry};
+mod constraints;
+mod datacontract;
+mod diff;
+mod document;
+mod issue;
+mod proto;
+mod schema;
+mod tabular;
+
+use bt_core::{log_stderr, BtError, Context, LogEntry, ToolError};
+use diff::Diff;
+use document::Format;
+use issue::{Code, Issue};
 use serde::{Deserialize, Serialize};
-use std::io::Read;
-use std::time::SystemTime;
+use std::path::Path;
+use std::process::ExitCode;
 
 #[derive(Debug, Deserialize)]
 struct ValidateInput {
     contract_path: String,
     output_path: String,
+    /// Output format; detected from the extension, then by content, when unset
+    #[serde(default)]
+    format: Option<Format>,
+    /// Contract format; detected the same way
+    #[serde(default)]
+    contract_format: Option<Format>,
+    /// Contract model the output must match; defaults to the output model
+    #[serde(default)]
+    model: Option<String>,
+    /// Decode the output as this protobuf message (e.g. `bitter.v1.Result`) instead of text
+    #[serde(default)]
+    proto_message: Option<String>,
+    /// Descriptor set holding `proto_message`; overrides `[validate.proto] descriptor_set`
+    #[serde(default)]
+    descriptor_set: Option<String>,
+    /// Run datacontract-cli for `datacontract.yaml` contracts; overrides `[validate.datacontract] enabled`
+    #[serde(default)]
+    datacontract: Option<bool>,
     #[serde(default)]
     context: Context,
 }
 
+/// The `[validate]` config table
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ValidateConfig {
+    datacontract: datacontract::DatacontractConfig,
+    proto: proto::ProtoConfig,
+    tabular: tabular::TabularConfig,
+}
+
 #[derive(Debug, Serialize)]
 struct ValidateOutput {
     valid: bool,
     errors: Vec<String>,
+    /// `errors` with a stable code each, in the same order
+    issues: Vec<Issue>,
+    /// Format the output was read as
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<Format>,
+    /// Rows checked in a CSV or Parquet output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rows: Option<u64>,
+    /// Validators that ran
+    checks: Vec<String>,
+    /// Expected vs actual, when validation fails
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<Diff>,
+    /// The diff as plain text, for the next generation attempt's feedback
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
     was_dry_run: bool,
 }
 
-fn main() {
-    let start = SystemTime::now();
-    let mut input_str = String::new();
-    if std::io::stdin().read_to_string(&mut input_str).is_err() {
-        eprintln!("Failed to read stdin");
-        std::process::exit(1);
-    }
-
-    let input: ValidateInput = match serde_json::from_str(&input_str) {
-        Ok(i) => i,
-        Err(e) => {
-            let log = LogEntry::error(format!("Invalid JSON input: {}", e), "unknown".to_string());
-            log_stderr(&log);
-            error_exit(format!("Invalid JSON: {}", e), "unknown".to_string(), start);
-        }
-    };
+fn main() -> ExitCode {
+    bt_core::run(validate)
+}
 
+fn validate(input: ValidateInput) -> Result<ValidateOutput, ToolError> {
     let trace_id = input.context.trace_id.clone();
     let dry_run = input.context.dry_run;
 
@@ -42,13 +85,17 @@ fn main() {
         let log = LogEntry::info("dry-run mode - skipping validation", trace_id.clone());
         log_stderr(&log);
 
-        let output = ValidateOutput {
+        return Ok(ValidateOutput {
             valid: true,
             errors: vec![],
+            issues: vec![],
+            format: None,
+            rows: None,
+            checks: vec![],
+            diff: None,
+            summary: None,
             was_dry_run: true,
-        };
-
-        success_exit(output, trace_id.clone(), start);
+        });
     }
 
     let log = LogEntry::info("validating output against contract", trace_id.clone())
@@ -56,33 +103,156 @@ fn main() {
         .with_extra("output", serde_json::Value::String(input.output_path.clone()));
     log_stderr(&log);
 
-    // Basic validation: check files exist
-    let contract_exists = std::path::Path::new(&input.contract_path).exists();
-    let output_exists = std::path::Path::new(&input.output_path).exists();
+    // Basic validation: check files exist. A missing file fails validation
+    // with an environmental issue rather than failing the tool.
+    let missing: Vec<Issue> = [("Contract", &input.contract_path), ("Output file", &input.output_path)]
+        .into_iter()
+        .filter(|(_, path)| !Path::new(path).exists())
+        .map(|(what, path)| Issue::new(Code::FileNotFound, format!("{} not found: {}", what, path)))
+        .collect();
+    if !missing.is_empty() {
+        let log = LogEntry::warn("validation input missing", trace_id.clone())
+            .with_extra("errors", serde_json::json!(missing.iter().map(|i| &i.message).collect::<Vec<_>>()));
+        log_stderr(&log);
+        return Ok(ValidateOutput {
+            valid: false,
+            errors: missing.iter().map(|i| i.message.clone()).collect(),
+            issues: missing,
+            format: None,
+            rows: None,
+            checks: vec!["exists".to_string()],
+            diff: None,
+            summary: None,
+            was_dry_run: false,
+        });
+    }
+
+    let mut config: ValidateConfig = bt_core::config::load_section("validate")?;
+    if let Some(enabled) = input.datacontract {
+        config.datacontract.enabled = enabled;
+    }
 
-    if !contract_exists {
-        error_exit(
-            format!("Contract not found: {}", input.contract_path),
-            trace_id,
-            start,
-        );
+    let mut issues: Vec<Issue> = vec![];
+    let mut diff = Diff::default();
+    let mut checks = vec!["exists".to_string()];
+    let contract = Path::new(&input.contract_path);
+    let output_path = Path::new(&input.output_path);
+
+    // A binary protobuf output, decoded up front; its JSON form feeds the schema check
+    let mut decoded = None;
+    if let Some(message) = &input.proto_message {
+        let descriptor_set = input.descriptor_set.clone().or(config.proto.descriptor_set.clone()).ok_or_else(|| {
+            BtError::InvalidInput("proto_message needs descriptor_set (input or [validate.proto])".to_string())
+        })?;
+        let descriptor = proto::message_descriptor(Path::new(&descriptor_set), message)?;
+        let bytes = std::fs::read(output_path)
+            .map_err(|e| BtError::Io(format!("Failed to read {}: {}", input.output_path, e)))?;
+        match proto::decode(descriptor, &bytes) {
+            Ok(message) => {
+                let problems = proto::check(&message);
+                diff.other.extend(problems.iter().map(|i| i.message.clone()));
+                issues.extend(problems);
+                decoded = Some(proto::to_json(&message));
+            }
+            Err(message) => {
+                diff.other.push(message.clone());
+                issues.push(Issue::new(Code::ParseError, message));
+            }
+        }
+        checks.push("protobuf".to_string());
     }
 
-    if !output_exists {
-        error_exit(
-            format!("Output file not found: {}", input.output_path),
-            trace_id,
-            start,
-        );
+    let contract_doc = document::load(contract, input.contract_format)?.map_err(BtError::InvalidInput)?;
+    let models = schema::models(&contract_doc.value).map_err(BtError::InvalidInput)?;
+    let mut format = None;
+    let mut rows = None;
+    if models.is_empty() {
+        let log = LogEntry::debug("contract defines no models, skipping schema check", trace_id.clone());
+        log_stderr(&log);
+    } else {
+        let model = schema::select(&models, input.model.as_deref()).map_err(BtError::InvalidInput)?;
+        let output_format = input.format.or_else(|| Format::from_path(output_path));
+        let output = match decoded {
+            Some(value) => Some(Ok(value)),
+            // Undecodable protobuf output is already reported
+            None if input.proto_message.is_some() => None,
+            None if output_format.is_some_and(Format::is_tabular) => {
+                let report = match output_format {
+                    Some(Format::Parquet) => tabular::check_parquet(output_path, model, &config.tabular)?,
+                    _ => tabular::check_csv(output_path, model, &config.tabular)?,
+                };
+                format = output_format;
+                match report {
+                    Ok(report) => {
+                        rows = Some(report.rows);
+                        issues.extend(report.issues);
+                        diff = report.diff;
+                    }
+                    Err(message) => {
+                        diff.other.push(message.clone());
+                        issues.push(Issue::new(Code::ParseError, message));
+                    }
+                }
+                None
+            }
+            None => Some(document::load(output_path, input.format)?.map(|doc| {
+                format = Some(doc.format);
+                doc.value
+            })),
+        };
+        match output {
+            Some(Ok(value)) => issues.extend(schema::check(model, &value, &mut diff)),
+            Some(Err(message)) => {
+                diff.other.push(message.clone());
+                issues.push(Issue::new(Code::ParseError, message));
+            }
+            None => {}
+        }
+        checks.push("schema".to_string());
     }
 
-    // For now, just verify files exist and are readable
-    // Full datacontract-cli validation would go here
-    let output = ValidateOutput {
-        valid: true,
-        errors: vec![],
-        was_dry_run: false,
-    };
+    if config.datacontract.enabled && datacontract::is_datacontract(contract) {
+        let log = LogEntry::debug("running datacontract-cli", trace_id.clone())
+            .with_extra("binary", serde_json::Value::String(config.datacontract.binary.clone()))
+            .with_extra("test", serde_json::Value::Bool(config.datacontract.test));
+        log_stderr(&log);
+        let problems = datacontract::check(
+            &config.datacontract,
+            contract,
+            output_path,
+            &trace_id,
+            input.context.timeout_seconds.map(std::time::Duration::from_secs),
+        )?;
+        diff.other.extend(problems.iter().cloned());
+        issues.extend(problems.into_iter().map(|message| Issue::new(Code::DatacontractFailed, message)));
+        checks.push("datacontract".to_string());
+    } else if config.datacontract.enabled {
+        let log = LogEntry::debug("contract is not a datacontract.yaml, skipping datacontract-cli", trace_id.clone());
+        log_stderr(&log);
+    }
 
-    success_exit(output, trace_id, start);
+    let errors: Vec<String> = issues.iter().map(|i| i.message.clone()).collect();
+    let log = LogEntry::info("validation complete", trace_id.clone())
+        .with_extra("valid", serde_json::Value::Bool(errors.is_empty()))
+        .with_extra("errors", serde_json::json!(errors.len()))
+        .with_extra("checks", serde_json::json!(checks));
+    log_stderr(&log);
+
+    let (diff, summary) = if errors.is_empty() {
+        (None, None)
+    } else {
+        let summary = diff.summary();
+        (Some(diff), Some(summary))
+    };
+    Ok(ValidateOutput {
+        valid: errors.is_empty(),
+        errors,
+        issues,
+        format,
+        rows,
+        checks,
+        diff,
+        summary,
+        was_dry_run: false,
+    })
 }
@@ -0,0 +1,227 @@
+// Binary protobuf outputs: decode against a descriptor set and check required fields and enums
+
+use crate::issue::{Code, Issue};
+use bt_core::BtError;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, Kind, MessageDescriptor, ReflectMessage, SerializeOptions,
+    Value,
+};
+use serde::Deserialize;
+use std::path::Path;
+
+/// `[validate.proto]` in the config file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProtoConfig {
+    /// Descriptor set compiled from the proto dir, e.g.
+    /// `protoc --include_imports --descriptor_set_out=protos.pb proto/*.proto`
+    pub descriptor_set: Option<String>,
+}
+
+/// The message type `name` (fully qualified, e.g. `bitter.v1.Result`) from a descriptor set file
+pub fn message_descriptor(descriptor_set: &Path, name: &str) -> Result<MessageDescriptor, BtError> {
+    let bytes = std::fs::read(descriptor_set).map_err(|e| {
+        BtError::NotFound(format!(
+            "Descriptor set {}: {}",
+            descriptor_set.display(),
+            e
+        ))
+    })?;
+    let pool = DescriptorPool::decode(bytes.as_slice()).map_err(|e| {
+        BtError::InvalidInput(format!(
+            "Invalid descriptor set {}: {}",
+            descriptor_set.display(),
+            e
+        ))
+    })?;
+    pool.get_message_by_name(name).ok_or_else(|| {
+        BtError::InvalidInput(format!(
+            "Message '{}' not found in descriptor set {}",
+            name,
+            descriptor_set.display()
+        ))
+    })
+}
+
+/// Decode `bytes` as `descriptor`; a decode failure is the output's fault, reported as a message
+pub fn decode(descriptor: MessageDescriptor, bytes: &[u8]) -> Result<DynamicMessage, String> {
+    let name = descriptor.full_name().to_string();
+    DynamicMessage::decode(descriptor, bytes)
+        .map_err(|e| format!("Output is not a valid {} message: {}", name, e))
+}
+
+/// Required fields that are unset and enum numbers the enum does not define,
+/// anywhere in the message tree
+pub fn check(message: &DynamicMessage) -> Vec<Issue> {
+    let mut errors = vec![];
+    check_message(message, "", &mut errors);
+    errors
+}
+
+/// The message as JSON, so the contract's schema checks apply to it as well.
+/// Field names stay as declared and 64-bit integers stay numbers, to line up
+/// with contract fields rather than the proto3 JSON mapping.
+pub fn to_json(message: &DynamicMessage) -> serde_json::Value {
+    let options = SerializeOptions::new()
+        .use_proto_field_name(true)
+        .stringify_64_bit_integers(false);
+    message
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .unwrap_or_default()
+}
+
+fn check_message(message: &DynamicMessage, path: &str, errors: &mut Vec<Issue>) {
+    for field in message.descriptor().fields() {
+        let path = if path.is_empty() {
+            field.name().to_string()
+        } else {
+            format!("{}.{}", path, field.name())
+        };
+        if field.cardinality() == prost_reflect::Cardinality::Required && !message.has_field(&field)
+        {
+            errors.push(Issue::new(
+                Code::SchemaMissingField,
+                format!("{}: required field is missing", path),
+            ));
+            continue;
+        }
+        if message.has_field(&field) {
+            check_value(&field.kind(), &message.get_field(&field), &path, errors);
+        }
+    }
+}
+
+fn check_value(kind: &Kind, value: &Value, path: &str, errors: &mut Vec<Issue>) {
+    match value {
+        Value::EnumNumber(number) => {
+            if let Kind::Enum(descriptor) = kind {
+                if descriptor.get_value(*number).is_none() {
+                    let error = format!(
+                        "{}: {} is not a value of enum {}",
+                        path,
+                        number,
+                        descriptor.full_name()
+                    );
+                    errors.push(Issue::new(Code::EnumViolation, error));
+                }
+            }
+        }
+        Value::Message(message) => check_message(message, path, errors),
+        Value::List(values) => {
+            for (i, value) in values.iter().enumerate() {
+                check_value(kind, value, &format!("{}[{}]", path, i), errors);
+            }
+        }
+        Value::Map(entries) => {
+            // Map values use the entry message's `value` field kind
+            let value_kind = match kind {
+                Kind::Message(entry) => entry.map_entry_value_field().kind(),
+                other => other.clone(),
+            };
+            for (key, value) in entries {
+                check_value(&value_kind, value, &format!("{}[{:?}]", path, key), errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_reflect::prost::Message;
+    use prost_reflect::prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+        FileDescriptorProto, FileDescriptorSet,
+    };
+
+    fn field(
+        name: &str,
+        number: i32,
+        label: Label,
+        kind: Type,
+        type_name: Option<&str>,
+    ) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(label as i32),
+            r#type: Some(kind as i32),
+            type_name: type_name.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_required_fields_and_enum_ranges() {
+        let file = FileDescriptorProto {
+            name: Some("result.proto".to_string()),
+            package: Some("bitter.v1".to_string()),
+            syntax: Some("proto2".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("Result".to_string()),
+                field: vec![
+                    field("name", 1, Label::Required, Type::String, None),
+                    field(
+                        "status",
+                        2,
+                        Label::Optional,
+                        Type::Enum,
+                        Some(".bitter.v1.Status"),
+                    ),
+                ],
+                ..Default::default()
+            }],
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("Status".to_string()),
+                value: vec![
+                    EnumValueDescriptorProto {
+                        name: Some("OK".to_string()),
+                        number: Some(0),
+                        ..Default::default()
+                    },
+                    EnumValueDescriptorProto {
+                        name: Some("FAILED".to_string()),
+                        number: Some(1),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let path = std::env::temp_dir().join(format!("validate-proto-{}.pb", std::process::id()));
+        std::fs::write(
+            &path,
+            FileDescriptorSet { file: vec![file] }.encode_to_vec(),
+        )
+        .unwrap();
+        let descriptor = message_descriptor(&path, "bitter.v1.Result").unwrap();
+        assert!(message_descriptor(&path, "bitter.v1.Missing").is_err());
+
+        let mut good = DynamicMessage::new(descriptor.clone());
+        good.set_field_by_name("name", Value::String("ok".to_string()));
+        good.set_field_by_name("status", Value::EnumNumber(1));
+        let decoded = decode(descriptor.clone(), &good.encode_to_vec()).unwrap();
+        assert!(check(&decoded).is_empty());
+        assert_eq!(
+            to_json(&decoded),
+            serde_json::json!({"name": "ok", "status": "FAILED"})
+        );
+
+        let mut bad = DynamicMessage::new(descriptor.clone());
+        bad.set_field_by_name("status", Value::EnumNumber(7));
+        let decoded = decode(descriptor.clone(), &bad.encode_to_vec()).unwrap();
+        assert_eq!(
+            crate::issue::messages(&check(&decoded)),
+            vec![
+                "name: required field is missing",
+                "status: 7 is not a value of enum bitter.v1.Status"
+            ]
+        );
+
+        assert!(decode(descriptor, b"\xff\xff\xff").is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,377 @@
+// Data Contract models and structural checks of an output document against one
+
+use crate::constraints::{Constraints, Rule};
+use crate::diff::{Diff, Mismatch};
+use crate::issue::{Code, Issue};
+use serde_json::Value;
+
+/// A field from a contract model
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    /// Contract type as written (`string`, `integer`, `timestamp`, ...); `None` accepts anything
+    pub kind: Option<String>,
+    pub required: bool,
+    /// Nested fields of an `object`
+    pub fields: Vec<Field>,
+    /// Element definition of an `array`
+    pub items: Option<Box<Field>>,
+    pub constraints: Constraints,
+}
+
+/// A named model from the contract's `models` table
+#[derive(Debug, Clone)]
+pub struct Model {
+    pub name: String,
+    pub fields: Vec<Field>,
+    /// Cross-field rules every record must satisfy
+    pub rules: Vec<Rule>,
+}
+
+/// Every model in a parsed contract, by name. Fails on a malformed
+/// constraint (bad pattern, rule without a comparison).
+pub fn models(contract: &Value) -> Result<Vec<Model>, String> {
+    let Some(models) = contract["models"].as_object() else {
+        return Ok(vec![]);
+    };
+    models
+        .iter()
+        .map(|(name, model)| {
+            let in_model = |e: String| format!("Model {}: {}", name, e);
+            let rules = model["rules"]
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            Ok(Model {
+                name: name.clone(),
+                fields: fields_of(model).map_err(in_model)?,
+                rules: rules
+                    .iter()
+                    .map(Rule::parse)
+                    .collect::<Result<_, _>>()
+                    .map_err(in_model)?,
+            })
+        })
+        .collect()
+}
+
+/// Fields as a `fields` mapping (the current spec) or a `columns` list (the
+/// older form these contracts were first written in). Columns carry no
+/// `required` flag, so each one is expected unless it says otherwise.
+fn fields_of(definition: &Value) -> Result<Vec<Field>, String> {
+    if let Some(fields) = definition["fields"].as_object() {
+        return fields
+            .iter()
+            .map(|(name, f)| field(name, f, false))
+            .collect();
+    }
+    definition["columns"]
+        .as_array()
+        .map(|columns| {
+            columns
+                .iter()
+                .filter_map(|c| Some(field(c["name"].as_str()?, c, true)))
+                .collect()
+        })
+        .unwrap_or(Ok(vec![]))
+}
+
+fn field(name: &str, definition: &Value, required_by_default: bool) -> Result<Field, String> {
+    Ok(Field {
+        name: name.to_string(),
+        kind: definition["type"].as_str().map(str::to_string),
+        required: definition["required"]
+            .as_bool()
+            .unwrap_or(required_by_default),
+        fields: fields_of(definition)?,
+        items: match definition.get("items") {
+            Some(items) => Some(Box::new(field("[]", items, false)?)),
+            None => None,
+        },
+        constraints: Constraints::parse(definition).map_err(|e| format!("{}: {}", name, e))?,
+    })
+}
+
+/// The model to check output against: `name` when given, else one called
+/// `output` or ending in `Output`, else the only model
+pub fn select<'a>(models: &'a [Model], name: Option<&str>) -> Result<&'a Model, String> {
+    if let Some(name) = name {
+        return models
+            .iter()
+            .find(|m| m.name == name)
+            .ok_or_else(|| format!("Contract has no model '{}'", name));
+    }
+    let output = models
+        .iter()
+        .find(|m| m.name.eq_ignore_ascii_case("output") || m.name.ends_with("Output"));
+    match (output, models) {
+        (Some(model), _) => Ok(model),
+        (None, [only]) => Ok(only),
+        (None, []) => Err("Contract defines no models".to_string()),
+        (None, _) => Err(format!(
+            "Contract has several models ({}); set `model` to pick one",
+            models
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Check `output` (one record, or an array of records) against `model`,
+/// adding what differs to `diff`
+pub fn check(model: &Model, output: &Value, diff: &mut Diff) -> Vec<Issue> {
+    let mut errors = vec![];
+    match output {
+        Value::Array(records) => {
+            for (i, record) in records.iter().enumerate() {
+                check_record(model, record, &format!("[{}]", i), &mut errors, diff);
+            }
+        }
+        record => check_record(model, record, "", &mut errors, diff),
+    }
+    errors
+}
+
+fn check_record(
+    model: &Model,
+    record: &Value,
+    path: &str,
+    errors: &mut Vec<Issue>,
+    diff: &mut Diff,
+) {
+    check_object(&model.fields, record, path, errors, diff);
+    if !record.is_object() {
+        return;
+    }
+    for rule in &model.rules {
+        if let Some(values) = rule.check(record) {
+            let error = format!(
+                "{}: rule {} ({}) failed with {}",
+                display(path),
+                rule.name,
+                rule.expression,
+                values
+            );
+            diff.other.push(error.clone());
+            errors.push(Issue::new(Code::RuleViolation, error));
+        }
+    }
+}
+
+fn check_object(
+    fields: &[Field],
+    value: &Value,
+    path: &str,
+    errors: &mut Vec<Issue>,
+    diff: &mut Diff,
+) {
+    let Some(object) = value.as_object() else {
+        mismatch(display(path), "object", value, errors, diff);
+        return;
+    };
+    let child = |name: &str| {
+        if path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", path, name)
+        }
+    };
+    for field in fields {
+        let path = child(&field.name);
+        match object.get(&field.name) {
+            None | Some(Value::Null) if field.required => {
+                errors.push(Issue::new(
+                    Code::SchemaMissingField,
+                    format!("{}: required field is missing", path),
+                ));
+                diff.missing.push(path);
+            }
+            None | Some(Value::Null) => {}
+            Some(value) => check_value(field, value, &path, errors, diff),
+        }
+    }
+    // Only a model that lists its fields says which ones are unexpected
+    if !fields.is_empty() {
+        diff.unexpected.extend(
+            object
+                .keys()
+                .filter(|key| !fields.iter().any(|f| &f.name == *key))
+                .map(|key| child(key)),
+        );
+    }
+}
+
+fn check_value(field: &Field, value: &Value, path: &str, errors: &mut Vec<Issue>, diff: &mut Diff) {
+    if let Some(kind) = &field.kind {
+        if !matches_kind(kind, value) {
+            mismatch(path, kind, value, errors, diff);
+            return;
+        }
+    }
+    for violation in field.constraints.check(value) {
+        let error = format!("{}: {}", path, violation);
+        diff.other.push(error.clone());
+        errors.push(Issue::new(violation.code, error));
+    }
+    if !field.fields.is_empty() {
+        check_object(&field.fields, value, path, errors, diff);
+    }
+    if let (Some(items), Some(elements)) = (&field.items, value.as_array()) {
+        for (i, element) in elements.iter().enumerate() {
+            check_value(items, element, &format!("{}[{}]", path, i), errors, diff);
+        }
+    }
+}
+
+fn mismatch(path: &str, expected: &str, value: &Value, errors: &mut Vec<Issue>, diff: &mut Diff) {
+    let article = if expected == "object" { "an " } else { "" };
+    let error = format!(
+        "{}: expected {}{}, got {}",
+        path,
+        article,
+        expected,
+        type_name(value)
+    );
+    errors.push(Issue::new(Code::TypeMismatch, error));
+    diff.mismatches.push(Mismatch {
+        path: path.to_string(),
+        expected: expected.to_string(),
+        actual: type_name(value).to_string(),
+    });
+}
+
+/// Whether a JSON value can hold a contract type; unknown types accept anything
+fn matches_kind(kind: &str, value: &Value) -> bool {
+    match kind.to_ascii_lowercase().as_str() {
+        "string" | "text" | "varchar" | "uuid" | "date" | "time" | "timestamp" | "timestamp_tz"
+        | "timestamp_ntz" => value.is_string(),
+        "integer" | "int" | "long" | "bigint" => value.is_i64() || value.is_u64(),
+        "number" | "numeric" | "decimal" | "float" | "double" => value.is_number(),
+        "boolean" | "bool" => value.is_boolean(),
+        "object" | "record" | "struct" | "map" => value.is_object(),
+        "array" => value.is_array(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn display(path: &str) -> &str {
+    if path.is_empty() {
+        "output"
+    } else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issue::messages;
+    use serde_json::json;
+
+    #[test]
+    fn test_columns_and_fields_models() {
+        let contract = json!({"models": {
+            "input": {"columns": [{"name": "text", "type": "string"}]},
+            "output": {"columns": [
+                {"name": "result", "type": "string"},
+                {"name": "original_length", "type": "integer"},
+            ]},
+        }});
+        let all = models(&contract).unwrap();
+        let output = select(&all, None).unwrap();
+        assert_eq!(output.name, "output");
+        assert!(check(
+            output,
+            &json!({"result": "Hi", "original_length": 2}),
+            &mut Diff::default()
+        )
+        .is_empty());
+        let mut diff = Diff::default();
+        let issues = check(
+            output,
+            &json!({"original_length": 2.5, "debug": true}),
+            &mut diff,
+        );
+        assert_eq!(
+            messages(&issues),
+            vec![
+                "result: required field is missing",
+                "original_length: expected integer, got number"
+            ]
+        );
+        assert_eq!(issues[0].code, Code::SchemaMissingField);
+        assert_eq!(issues[1].code, Code::TypeMismatch);
+        assert_eq!(diff.missing, vec!["result"]);
+        assert_eq!(diff.unexpected, vec!["debug"]);
+        assert_eq!(
+            diff.mismatches[0].to_string(),
+            "original_length must be integer, got number"
+        );
+        assert!(diff.other.is_empty());
+
+        let contract = json!({"models": {"Orders": {"fields": {
+            "id": {"type": "string", "required": true},
+            "note": {"type": "string"},
+            "lines": {"type": "array", "items": {"type": "object", "fields": {"qty": {"type": "integer", "required": true}}}},
+        }}}});
+        let all = models(&contract).unwrap();
+        let orders = select(&all, Some("Orders")).unwrap();
+        assert_eq!(
+            messages(&check(
+                orders,
+                &json!([{"id": "a", "lines": [{"qty": 1}, {}]}, {"id": 7}]),
+                &mut Diff::default()
+            )),
+            vec![
+                "[0].lines[1].qty: required field is missing",
+                "[1].id: expected string, got integer"
+            ]
+        );
+        assert!(select(&all, Some("Nope")).is_err());
+
+        let contract = json!({"models": {"Booking": {
+            "fields": {"nights": {"type": "integer", "minimum": 1}, "status": {"type": "string", "enum": ["held", "paid"]}},
+            "rules": [{"name": "checkout_after_checkin", "check": "checkout > checkin"}],
+        }}});
+        let all = models(&contract).unwrap();
+        let issues = check(
+            &all[0],
+            &json!({"nights": 0, "status": "gone", "checkin": "2024-05-02", "checkout": "2024-05-01"}),
+            &mut Diff::default(),
+        );
+        let codes: Vec<Code> = issues.iter().map(|i| i.code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                Code::RangeViolation,
+                Code::EnumViolation,
+                Code::RuleViolation
+            ]
+        );
+        assert_eq!(
+            messages(&issues),
+            vec![
+                "nights: 0 violates minimum 1",
+                "status: \"gone\" violates enum [\"held\",\"paid\"]",
+                "output: rule checkout_after_checkin (checkout > checkin) failed with checkout=\"2024-05-01\", checkin=\"2024-05-02\"",
+            ]
+        );
+        assert!(
+            models(&json!({"models": {"Bad": {"fields": {"code": {"pattern": "("}}}}})).is_err()
+        );
+    }
+}
@@ -0,0 +1,568 @@
+// CSV and Parquet outputs: column presence, types, nullability and constraints, with sampled row errors
+
+use crate::constraints::Rule;
+use crate::diff::{Diff, Mismatch};
+use crate::issue::{Code, Issue};
+use crate::schema::{Field, Model};
+use bt_core::BtError;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field as ParquetField;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// `[validate.tabular]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TabularConfig {
+    /// Offending rows quoted per column and violation
+    pub sample: usize,
+    /// Stop after this many rows; `None` checks the whole file
+    pub max_rows: Option<u64>,
+}
+
+impl Default for TabularConfig {
+    fn default() -> Self {
+        Self {
+            sample: 5,
+            max_rows: None,
+        }
+    }
+}
+
+/// One value read from a table, typed where the file format carries types
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Null,
+    /// Untyped CSV text, checked by whether it parses as the declared type
+    Text(String),
+    Bool,
+    Int,
+    Float,
+    Str,
+    Date,
+    Timestamp,
+    Other,
+}
+
+/// Outcome of checking a table
+#[derive(Debug, Default)]
+pub struct Report {
+    pub rows: u64,
+    pub issues: Vec<Issue>,
+    pub diff: Diff,
+}
+
+/// Violations of one kind in one column: how many, and the first few rows
+struct Tally {
+    code: Code,
+    count: u64,
+    samples: Vec<String>,
+}
+
+pub fn check_csv(
+    path: &Path,
+    model: &Model,
+    config: &TabularConfig,
+) -> Result<Result<Report, String>, BtError> {
+    let delimiter = if path.extension().is_some_and(|e| e == "tsv") {
+        b'\t'
+    } else {
+        b','
+    };
+    let mut reader = match csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_path(path)
+    {
+        Ok(reader) => reader,
+        Err(e) => {
+            return Err(BtError::Io(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    let header: Vec<String> = match reader.headers() {
+        Ok(header) => header.iter().map(|h| h.trim().to_string()).collect(),
+        Err(e) => return Ok(Err(format!("{} is not valid CSV: {}", path.display(), e))),
+    };
+
+    let mut checker = Checker::new(model, &header, config);
+    for record in reader.records() {
+        if checker.done() {
+            break;
+        }
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => return Ok(Err(format!("{} is not valid CSV: {}", path.display(), e))),
+        };
+        let cells = record
+            .iter()
+            .map(|v| {
+                if v.is_empty() {
+                    (Cell::Null, Value::Null)
+                } else {
+                    (Cell::Text(v.to_string()), Value::String(v.to_string()))
+                }
+            })
+            .collect();
+        checker.row(cells);
+    }
+    Ok(Ok(checker.finish()))
+}
+
+pub fn check_parquet(
+    path: &Path,
+    model: &Model,
+    config: &TabularConfig,
+) -> Result<Result<Report, String>, BtError> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| BtError::Io(format!("Failed to read {}: {}", path.display(), e)))?;
+    let reader = match SerializedFileReader::new(file) {
+        Ok(reader) => reader,
+        Err(e) => {
+            return Ok(Err(format!(
+                "{} is not valid Parquet: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    let header: Vec<String> = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .map(|f| f.name().to_string())
+        .collect();
+
+    let mut checker = Checker::new(model, &header, config);
+    for row in reader {
+        if checker.done() {
+            break;
+        }
+        let row = match row {
+            Ok(row) => row,
+            Err(e) => {
+                return Ok(Err(format!(
+                    "{} is not valid Parquet: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        checker.row(
+            row.get_column_iter()
+                .map(|(_, field)| (cell(field), field.to_json_value()))
+                .collect(),
+        );
+    }
+    Ok(Ok(checker.finish()))
+}
+
+fn cell(field: &ParquetField) -> Cell {
+    match field {
+        ParquetField::Null => Cell::Null,
+        ParquetField::Bool(_) => Cell::Bool,
+        ParquetField::Byte(_)
+        | ParquetField::Short(_)
+        | ParquetField::Int(_)
+        | ParquetField::Long(_)
+        | ParquetField::UByte(_)
+        | ParquetField::UShort(_)
+        | ParquetField::UInt(_)
+        | ParquetField::ULong(_) => Cell::Int,
+        ParquetField::Float16(_)
+        | ParquetField::Float(_)
+        | ParquetField::Double(_)
+        | ParquetField::Decimal(_) => Cell::Float,
+        ParquetField::Str(_) => Cell::Str,
+        ParquetField::Date(_) => Cell::Date,
+        ParquetField::TimestampMillis(_) | ParquetField::TimestampMicros(_) => Cell::Timestamp,
+        _ => Cell::Other,
+    }
+}
+
+/// Checks rows against the model's fields, matched to table columns by name
+struct Checker<'a> {
+    header: &'a [String],
+    /// Contract field for each table column, if any
+    columns: Vec<Option<&'a Field>>,
+    rules: &'a [Rule],
+    issues: Vec<Issue>,
+    diff: Diff,
+    /// Keyed by (column, what is wrong), in first-seen order
+    tallies: Vec<((String, String), Tally)>,
+    index: HashMap<(String, String), usize>,
+    rows: u64,
+    config: &'a TabularConfig,
+}
+
+impl<'a> Checker<'a> {
+    fn new(model: &'a Model, header: &'a [String], config: &'a TabularConfig) -> Self {
+        let declared = |name: &String| model.fields.iter().any(|f| &f.name == name);
+        let diff = Diff {
+            missing: model
+                .fields
+                .iter()
+                .filter(|f| f.required && !header.contains(&f.name))
+                .map(|f| f.name.clone())
+                .collect(),
+            // Only a model that lists its fields says which columns are unexpected
+            unexpected: if model.fields.is_empty() {
+                vec![]
+            } else {
+                header.iter().filter(|h| !declared(h)).cloned().collect()
+            },
+            ..Diff::default()
+        };
+        let issues = diff
+            .missing
+            .iter()
+            .map(|name| {
+                Issue::new(
+                    Code::SchemaMissingField,
+                    format!("{}: required column is missing", name),
+                )
+            })
+            .collect();
+        Self {
+            header,
+            columns: header
+                .iter()
+                .map(|h| model.fields.iter().find(|f| &f.name == h))
+                .collect(),
+            rules: &model.rules,
+            issues,
+            diff,
+            tallies: vec![],
+            index: HashMap::new(),
+            rows: 0,
+            config,
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.config.max_rows.is_some_and(|max| self.rows >= max)
+    }
+
+    /// One row: each cell's type, and its value as JSON for constraints and rules
+    fn row(&mut self, cells: Vec<(Cell, Value)>) {
+        self.rows += 1;
+        let mut record = serde_json::Map::new();
+        for (i, (cell, value)) in cells.into_iter().enumerate() {
+            let Some(Some(field)) = self.columns.get(i).copied() else {
+                continue;
+            };
+            if cell == Cell::Null {
+                if field.required {
+                    self.record(
+                        Code::SchemaMissingField,
+                        &field.name,
+                        "null but required".to_string(),
+                        None,
+                    );
+                }
+                continue;
+            }
+            match field
+                .kind
+                .as_deref()
+                .filter(|kind| !matches_kind(kind, &cell))
+            {
+                Some(kind) => {
+                    let sample = format!("{:?}", sample_value(&cell));
+                    self.record(
+                        Code::TypeMismatch,
+                        &field.name,
+                        format!("not {}", kind),
+                        Some(sample),
+                    )
+                }
+                None => {
+                    let value = typed(field.kind.as_deref(), value);
+                    for violation in field.constraints.check(&value) {
+                        let what = format!("violate {}", violation.constraint);
+                        self.record(violation.code, &field.name, what, Some(violation.value));
+                    }
+                    record.insert(self.header[i].clone(), value);
+                }
+            }
+        }
+        let record = Value::Object(record);
+        for rule in self.rules {
+            if let Some(values) = rule.check(&record) {
+                let what = format!("violate `{}`", rule.expression);
+                self.record(
+                    Code::RuleViolation,
+                    &format!("rule {}", rule.name),
+                    what,
+                    Some(values),
+                );
+            }
+        }
+    }
+
+    /// Tally a violation; `detail` follows the row number in the samples
+    fn record(&mut self, code: Code, column: &str, what: String, detail: Option<String>) {
+        let key = (column.to_string(), what);
+        let at = *self.index.entry(key.clone()).or_insert_with(|| {
+            let tally = Tally {
+                code,
+                count: 0,
+                samples: vec![],
+            };
+            self.tallies.push((key, tally));
+            self.tallies.len() - 1
+        });
+        let tally = &mut self.tallies[at].1;
+        tally.count += 1;
+        if tally.samples.len() < self.config.sample {
+            tally.samples.push(match detail {
+                Some(detail) => format!("row {}: {}", self.rows, detail),
+                None => format!("row {}", self.rows),
+            });
+        }
+    }
+
+    fn finish(mut self) -> Report {
+        for ((column, what), tally) in self.tallies {
+            let error = format!(
+                "{}: {} of {} rows {} (e.g. {})",
+                column,
+                tally.count,
+                self.rows,
+                what,
+                tally.samples.join(", ")
+            );
+            match what
+                .strip_prefix("not ")
+                .filter(|_| tally.code == Code::TypeMismatch)
+            {
+                Some(expected) => self.diff.mismatches.push(Mismatch {
+                    path: column,
+                    expected: expected.to_string(),
+                    actual: tally.samples.join(", "),
+                }),
+                None => self.diff.other.push(error.clone()),
+            }
+            self.issues.push(Issue::new(tally.code, error));
+        }
+        Report {
+            rows: self.rows,
+            issues: self.issues,
+            diff: self.diff,
+        }
+    }
+}
+
+fn sample_value(cell: &Cell) -> String {
+    match cell {
+        Cell::Text(text) => text.clone(),
+        other => format!("{:?}", other).to_lowercase(),
+    }
+}
+
+/// CSV text as the JSON value its declared type implies, so numeric
+/// ranges and rules compare numbers rather than strings
+fn typed(kind: Option<&str>, value: Value) -> Value {
+    let Value::String(text) = &value else {
+        return value;
+    };
+    match kind.map(str::to_ascii_lowercase).as_deref() {
+        Some(
+            "integer" | "int" | "long" | "bigint" | "number" | "numeric" | "decimal" | "float"
+            | "double",
+        ) => serde_json::from_str::<serde_json::Number>(text)
+            .map(Value::Number)
+            .unwrap_or(value),
+        Some("boolean" | "bool") => Value::Bool(text.eq_ignore_ascii_case("true")),
+        _ => value,
+    }
+}
+
+/// Whether a cell can hold a contract type; CSV text must parse as it
+fn matches_kind(kind: &str, cell: &Cell) -> bool {
+    let kind = kind.to_ascii_lowercase();
+    if let Cell::Text(text) = cell {
+        return match kind.as_str() {
+            "integer" | "int" | "long" | "bigint" => {
+                text.parse::<i64>().is_ok() || text.parse::<u64>().is_ok()
+            }
+            "number" | "numeric" | "decimal" | "float" | "double" => text.parse::<f64>().is_ok(),
+            "boolean" | "bool" => matches!(text.to_ascii_lowercase().as_str(), "true" | "false"),
+            "date" => is_date(text),
+            "timestamp" | "timestamp_tz" | "timestamp_ntz" => {
+                text.len() > 10
+                    && is_date(&text[..10])
+                    && matches!(text.as_bytes()[10], b'T' | b' ')
+            }
+            _ => true,
+        };
+    }
+    match kind.as_str() {
+        "integer" | "int" | "long" | "bigint" => *cell == Cell::Int,
+        "number" | "numeric" | "decimal" | "float" | "double" => {
+            matches!(cell, Cell::Int | Cell::Float)
+        }
+        "boolean" | "bool" => *cell == Cell::Bool,
+        "string" | "text" | "varchar" | "uuid" => *cell == Cell::Str,
+        "date" => *cell == Cell::Date,
+        "timestamp" | "timestamp_tz" | "timestamp_ntz" => *cell == Cell::Timestamp,
+        _ => true,
+    }
+}
+
+/// `YYYY-MM-DD`
+fn is_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| {
+            if i == 4 || i == 7 {
+                *b == b'-'
+            } else {
+                b.is_ascii_digit()
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issue::messages;
+    use crate::schema;
+    use parquet::data_type::{ByteArrayType, Int64Type};
+    use parquet::file::writer::SerializedFileWriter;
+    use std::sync::Arc;
+
+    fn model() -> Model {
+        let contract = serde_json::json!({"models": {"orders": {"fields": {
+            "id": {"type": "integer", "required": true},
+            "placed": {"type": "date"},
+            "note": {"type": "string"},
+        }}}});
+        schema::models(&contract).unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_csv_types_nulls_and_sampling() {
+        let path = std::env::temp_dir().join(format!("validate-tab-{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "id,placed,extra\n1,2024-01-02,x\nabc,2024-13,y\n,2024-01-03,z\nx2,,w\n",
+        )
+        .unwrap();
+        let config = TabularConfig {
+            sample: 1,
+            max_rows: None,
+        };
+        let report = check_csv(&path, &model(), &config).unwrap().unwrap();
+        assert_eq!(report.rows, 4);
+        assert_eq!(
+            messages(&report.issues),
+            vec![
+                "id: 2 of 4 rows not integer (e.g. row 2: \"abc\")",
+                "placed: 1 of 4 rows not date (e.g. row 2: \"2024-13\")",
+                "id: 1 of 4 rows null but required (e.g. row 3)",
+            ]
+        );
+        assert_eq!(report.diff.unexpected, vec!["extra"]);
+        assert_eq!(
+            report.diff.mismatches[1].to_string(),
+            "placed must be date, got row 2: \"2024-13\""
+        );
+        assert_eq!(
+            report.diff.other,
+            vec!["id: 1 of 4 rows null but required (e.g. row 3)"]
+        );
+
+        std::fs::write(&path, "placed\n2024-01-01\n").unwrap();
+        let report = check_csv(&path, &model(), &TabularConfig::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            messages(&report.issues),
+            vec!["id: required column is missing"]
+        );
+        assert_eq!(report.issues[0].code, Code::SchemaMissingField);
+        assert_eq!(report.diff.missing, vec!["id"]);
+
+        let contract = serde_json::json!({"models": {"stays": {
+            "fields": {"nights": {"type": "integer", "maximum": 30}, "status": {"type": "string", "enum": ["held", "paid"]}, "from": {"type": "date"}, "to": {"type": "date"}},
+            "rules": [{"name": "ends_after_start", "check": "to > from"}],
+        }}});
+        let stays = schema::models(&contract).unwrap().remove(0);
+        std::fs::write(
+            &path,
+            "nights,status,from,to\n3,paid,2024-01-01,2024-01-04\n45,gone,2024-02-05,2024-02-01\n",
+        )
+        .unwrap();
+        let report = check_csv(&path, &stays, &TabularConfig::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            messages(&report.issues),
+            vec![
+                "nights: 1 of 2 rows violate maximum 30 (e.g. row 2: 45)",
+                "status: 1 of 2 rows violate enum [\"held\",\"paid\"] (e.g. row 2: \"gone\")",
+                "rule ends_after_start: 1 of 2 rows violate `to > from` (e.g. row 2: to=\"2024-02-01\", from=\"2024-02-05\")",
+            ]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_parquet_physical_types() {
+        let path =
+            std::env::temp_dir().join(format!("validate-tab-{}.parquet", std::process::id()));
+        let schema = Arc::new(
+            parquet::schema::parser::parse_message_type(
+                "message orders { required int64 id; optional binary note (UTF8); }",
+            )
+            .unwrap(),
+        );
+        let mut writer = SerializedFileWriter::new(
+            std::fs::File::create(&path).unwrap(),
+            schema,
+            Default::default(),
+        )
+        .unwrap();
+        let mut group = writer.next_row_group().unwrap();
+        let mut id = group.next_column().unwrap().unwrap();
+        id.typed::<Int64Type>()
+            .write_batch(&[1, 2], None, None)
+            .unwrap();
+        id.close().unwrap();
+        let mut note = group.next_column().unwrap().unwrap();
+        note.typed::<ByteArrayType>()
+            .write_batch(&["a".into()], Some(&[1, 0]), None)
+            .unwrap();
+        note.close().unwrap();
+        group.close().unwrap();
+        writer.close().unwrap();
+
+        let report = check_parquet(&path, &model(), &TabularConfig::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.rows, 2);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+
+        let contract = serde_json::json!({"models": {"orders": {"fields": {"id": {"type": "string"}, "note": {"type": "string", "required": true}}}}});
+        let strict = schema::models(&contract).unwrap().remove(0);
+        let report = check_parquet(&path, &strict, &TabularConfig::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            messages(&report.issues),
+            vec![
+                "id: 2 of 2 rows not string (e.g. row 1: \"int\", row 2: \"int\")",
+                "note: 1 of 2 rows null but required (e.g. row 2)"
+            ]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+// Heuristics for replies without a code fence: what a first line of code looks like
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// First-line prefixes that mark code in the languages the pipeline generates
+const CODE_STARTS: &[&str] = &[
+    "#!/",
+    "def ",
+    "fn ",
+    "func ",
+    "function ",
+    "let ",
+    "const ",
+    "import ",
+    "use ",
+    "from ",
+    "{",
+    "[",
+    "//",
+    "#!",
+    "# ",
+    // Nushell specific
+    "def main",
+    "export def",
+    "module ",
+];
+
+/// Whether the first line of `text` looks like code
+pub fn looks_like_code(text: &str) -> bool {
+    let first_line = text.lines().next().unwrap_or("").trim();
+    CODE_STARTS
+        .iter()
+        .any(|start| first_line.starts_with(start))
+}
+
+/// Everything from the first line that looks like code onward
+pub fn extract_code_from_mixed(input: &str) -> Option<String> {
+    let lines: Vec<&str> = input.lines().collect();
+    let start = lines.iter().position(|line| looks_like_code(line))?;
+    Some(lines[start..].join("\n"))
+}
+
+/// Code following a lead-in such as "Here is the script:" or "I've written the function:"
+pub fn code_after_prefix(input: &str) -> Option<String> {
+    static PREFIXES: OnceLock<[Regex; 2]> = OnceLock::new();
+    let prefixes = PREFIXES.get_or_init(|| {
+        [
+            r"(?s)(?:Here is|Here's|Below is|The following is)[^:]*:\s*\n+(.*)",
+            r"(?s)(?:I've|I have) (?:created|written|generated)[^:]*:\s*\n+(.*)",
+        ]
+        .map(|pattern| Regex::new(pattern).expect("valid built-in pattern"))
+    });
+    prefixes.iter().find_map(|re| {
+        let content = re.captures(input)?.get(1)?.as_str().trim();
+        (!content.is_empty() && looks_like_code(content)).then(|| content.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_starts() {
+        assert!(looks_like_code("export def greet [] {}"));
+        assert!(looks_like_code("  fn main() {}\nmore"));
+        assert!(!looks_like_code("Sure, here you go"));
+        assert_eq!(
+            extract_code_from_mixed("Sure:\nlet x = 1\nx"),
+            Some("let x = 1\nx".to_string())
+        );
+        assert_eq!(extract_code_from_mixed("no code here"), None);
+        assert_eq!(
+            code_after_prefix("Here is the script:\n\ndef main [] {}"),
+            Some("def main [] {}".to_string())
+        );
+        assert_eq!(code_after_prefix("Here is why:\n\nit fails"), None);
+    }
+}
@@ -0,0 +1,314 @@
+// Extraction of code and JSON from chatty LLM output, shared by the CLI and the bitter-truth tools
+//
+// Nothing here prints or exits: callers get the extracted text and where it
+// came from, or an `ExtractError`, and decide what to log.
+
+pub mod heuristics;
+
+use regex::Regex;
+use std::fmt;
+use std::sync::OnceLock;
+
+pub use heuristics::{extract_code_from_mixed, looks_like_code};
+
+/// Extracted text and the rule that found it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extraction {
+    pub content: String,
+    pub source: Source,
+}
+
+/// Which rule produced an `Extraction`, most to least reliable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// A fenced markdown block
+    CodeBlock,
+    /// The whole input already looked like code
+    RawCode,
+    /// Everything from the first line that looks like code
+    MixedText,
+    /// Text after a lead-in such as "Here is the code:"
+    AfterPrefix,
+    /// A bare `{...}` object in prose
+    RawJson,
+}
+
+impl Source {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Source::CodeBlock => "code block",
+            Source::RawCode => "raw code",
+            Source::MixedText => "code in mixed text",
+            Source::AfterPrefix => "code after LLM prefix",
+            Source::RawJson => "raw JSON object",
+        }
+    }
+}
+
+/// One fenced markdown block
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    /// The fence's language tag, if it had one
+    pub lang: Option<String>,
+    pub content: String,
+}
+
+/// Which block to take when a reply has several
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Selection {
+    #[default]
+    First,
+    /// 0-based
+    Index(usize),
+    Last,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtractError {
+    /// A matching code block was found but held nothing
+    EmptyBlock,
+    /// `Selection::Index` past the last block
+    NoSuchBlock {
+        index: usize,
+        count: usize,
+    },
+    /// No block and nothing that looks like code; carries the start of the input
+    NoCode {
+        preview: String,
+    },
+    NoJson,
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractError::EmptyBlock => f.write_str("Code block was empty"),
+            ExtractError::NoSuchBlock { index, count } => {
+                write!(
+                    f,
+                    "No code block at index {}; input has {} block(s)",
+                    index, count
+                )
+            }
+            ExtractError::NoCode { preview } => write!(
+                f,
+                "No code block found in input. Input preview: {}...",
+                preview
+            ),
+            ExtractError::NoJson => f.write_str("No JSON found in input"),
+        }
+    }
+}
+
+impl std::error::Error for ExtractError {}
+
+/// Extract code from the first markdown block (tagged `lang`, when given),
+/// falling back to the `heuristics` for replies without a fence
+pub fn extract_code_block(input: &str, lang: Option<&str>) -> Result<Extraction, ExtractError> {
+    extract_code_block_at(input, lang, Selection::First)
+}
+
+/// Like `extract_code_block`, taking the `selection`ed block. Without any
+/// fenced block the heuristic match counts as the only one.
+pub fn extract_code_block_at(
+    input: &str,
+    lang: Option<&str>,
+    selection: Selection,
+) -> Result<Extraction, ExtractError> {
+    let blocks = code_blocks(input, lang);
+    let count = blocks.len();
+    if let Selection::Index(index) = selection {
+        // Without a fence, the heuristic match is block 0
+        if index >= count.max(1) {
+            return Err(ExtractError::NoSuchBlock { index, count });
+        }
+    }
+    let block = match selection {
+        Selection::First => blocks.into_iter().next(),
+        Selection::Last => blocks.into_iter().last(),
+        Selection::Index(index) => blocks.into_iter().nth(index),
+    };
+    if let Some(block) = block {
+        if block.content.is_empty() {
+            return Err(ExtractError::EmptyBlock);
+        }
+        return Ok(found(&block.content, Source::CodeBlock));
+    }
+
+    let trimmed = input.trim();
+    if looks_like_code(trimmed) {
+        return Ok(found(trimmed, Source::RawCode));
+    }
+    if let Some(code) = extract_code_from_mixed(input) {
+        return Ok(found(&code, Source::MixedText));
+    }
+    if let Some(code) = heuristics::code_after_prefix(input) {
+        return Ok(found(&code, Source::AfterPrefix));
+    }
+
+    Err(ExtractError::NoCode {
+        preview: input.chars().take(100).collect(),
+    })
+}
+
+/// Every fenced block in order, or only those tagged `lang` (case-insensitively)
+pub fn code_blocks(input: &str, lang: Option<&str>) -> Vec<Block> {
+    cached(&ANY_BLOCK, r"(?s)```([\w+#.-]+)?[ \t]*\n?(.*?)```")
+        .captures_iter(input)
+        .map(|caps| Block {
+            lang: caps.get(1).map(|m| m.as_str().to_string()),
+            content: caps
+                .get(2)
+                .map(|m| m.as_str().trim())
+                .unwrap_or("")
+                .to_string(),
+        })
+        .filter(|block| {
+            lang.is_none_or(|lang| {
+                block
+                    .lang
+                    .as_deref()
+                    .is_some_and(|tag| tag.eq_ignore_ascii_case(lang))
+            })
+        })
+        .collect()
+}
+
+/// Extract a JSON object from a markdown block, else the first bare object in the text.
+/// The result is not parsed; callers that need a value should `serde_json::from_str` it.
+pub fn extract_json(input: &str) -> Result<Extraction, ExtractError> {
+    let block = cached(&JSON_BLOCK, r"(?s)```(?:json)?\s*\n?(\{.*?\})\s*```");
+    if let Some(caps) = block.captures(input) {
+        return Ok(found(
+            caps.get(1).map(|m| m.as_str()).unwrap_or(""),
+            Source::CodeBlock,
+        ));
+    }
+
+    // Objects nested at most one level deep
+    let raw = cached(&RAW_JSON, r"(?s)(\{[^{}]*(?:\{[^{}]*\}[^{}]*)*\})");
+    if let Some(caps) = raw.captures(input) {
+        return Ok(found(
+            caps.get(1).map(|m| m.as_str()).unwrap_or(""),
+            Source::RawJson,
+        ));
+    }
+
+    Err(ExtractError::NoJson)
+}
+
+static ANY_BLOCK: OnceLock<Regex> = OnceLock::new();
+static JSON_BLOCK: OnceLock<Regex> = OnceLock::new();
+static RAW_JSON: OnceLock<Regex> = OnceLock::new();
+
+/// A fixed pattern, compiled on first use
+fn cached(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid built-in pattern"))
+}
+
+fn found(content: &str, source: Source) -> Extraction {
+    Extraction {
+        content: content.to_string(),
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_nushell_block() {
+        let input = r#"Here is the script:
+
+```nushell
+#!/usr/bin/env nu
+def main [] {
+    print "hello"
+}
+```
+
+Hope this helps!"#;
+
+        let result = extract_code_block(input, Some("nushell")).unwrap();
+        assert_eq!(result.source, Source::CodeBlock);
+        assert!(result.content.contains("def main"));
+        assert!(result.content.contains("print \"hello\""));
+    }
+
+    #[test]
+    fn test_extract_json() {
+        let input = r#"Here is the data:
+```json
+{"success": true, "data": {"value": 42}}
+```
+"#;
+        let result = extract_json(input).unwrap();
+        assert!(result.content.contains("success"));
+        assert_eq!(
+            extract_json("The answer is {\"a\": {\"b\": 1}}.")
+                .unwrap()
+                .source,
+            Source::RawJson
+        );
+        assert_eq!(extract_json("no braces here"), Err(ExtractError::NoJson));
+    }
+
+    #[test]
+    fn test_raw_code() {
+        let input = "#!/usr/bin/env nu\ndef main [] { print 'test' }";
+        let result = extract_code_block(input, None).unwrap();
+        assert_eq!(result.source, Source::RawCode);
+        assert!(result.content.contains("def main"));
+    }
+
+    #[test]
+    fn test_fallbacks_and_errors() {
+        let mixed = extract_code_block("Sure thing.\nimport os\nprint(os.getcwd())", None).unwrap();
+        assert_eq!(mixed.source, Source::MixedText);
+        assert_eq!(mixed.content, "import os\nprint(os.getcwd())");
+
+        assert_eq!(
+            extract_code_block("```python\n\n```", Some("python")),
+            Err(ExtractError::EmptyBlock)
+        );
+    }
+
+    #[test]
+    fn test_block_selection() {
+        let input = "Two files.\n```rust\nfn a() {}\n```\nand\n```toml\n[package]\n```\nthen\n```rust\nfn b() {}\n```\n";
+        let blocks = code_blocks(input, None);
+        let langs: Vec<_> = blocks
+            .iter()
+            .map(|b| b.lang.as_deref().unwrap_or(""))
+            .collect();
+        assert_eq!(langs, vec!["rust", "toml", "rust"]);
+        assert_eq!(code_blocks(input, Some("RUST")).len(), 2);
+
+        let at = |lang, selection| extract_code_block_at(input, lang, selection).map(|e| e.content);
+        assert_eq!(at(None, Selection::Index(1)), Ok("[package]".to_string()));
+        assert_eq!(at(None, Selection::Last), Ok("fn b() {}".to_string()));
+        assert_eq!(
+            at(Some("rust"), Selection::Index(1)),
+            Ok("fn b() {}".to_string())
+        );
+        assert_eq!(
+            at(None, Selection::Index(3)),
+            Err(ExtractError::NoSuchBlock { index: 3, count: 3 })
+        );
+
+        // No fence: the heuristic match is the one and only block
+        assert_eq!(
+            extract_code_block_at("let x = 1", None, Selection::Last)
+                .unwrap()
+                .source,
+            Source::RawCode
+        );
+        assert!(extract_code_block_at("let x = 1", None, Selection::Index(1)).is_err());
+        let err = extract_code_block("I cannot help with that.", None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No code block found in input. Input preview: I cannot help with that...."
+        );
+    }
+}